@@ -1,11 +1,21 @@
+use crate::error::{self, Result};
 use serde::{Serialize, Deserialize};
 use std::collections::BTreeMap;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Default location of the letter statistics written by `analyze`.
+pub const STATS_PATH: &str = "letter_stats.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LetterStats {
     pub counts: BTreeMap<char, [u32; 5]>,
 }
 
+impl Default for LetterStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LetterStats {
     pub fn new() -> Self {
         let mut counts = BTreeMap::new();
@@ -36,4 +46,54 @@ impl LetterStats {
 
         stats
     }
+
+    pub fn load(path: &str) -> Result<Self> {
+        error::parse_json(&error::read_file(path)?, path)
+    }
+
+    /// The statistics as pretty JSON, with every letter's counts on one line.
+    pub fn to_json(&self) -> String {
+        let json = serde_json::to_string_pretty(self).expect("letter stats serialize to JSON");
+
+        // This regex joins lines between '[' and ']'
+        let re = regex::Regex::new(r"\[\s*((?:\d+,\s*)*\d+)\s*\]").unwrap();
+        re.replace_all(&json, |caps: &regex::Captures| {
+            let inner = caps[1]
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("[{}]", inner)
+        })
+        .to_string()
+    }
+
+    /// Letter statistics over the remaining candidates, for ranking them. Positions
+    /// where every candidate has the same letter (such as confirmed greens) are
+    /// skipped, since that letter tells the candidates apart no more.
+    pub fn from_candidates(words: &[&str]) -> Self {
+        let mut stats = Self::new();
+
+        let mut known: [Option<u8>; 5] = [None; 5];
+        if let Some(first) = words.first() {
+            for (i, slot) in known.iter_mut().enumerate() {
+                let letter = first.as_bytes()[i];
+                if words.iter().all(|w| w.as_bytes()[i] == letter) {
+                    *slot = Some(letter);
+                }
+            }
+        }
+
+        for word in words {
+            for (i, &b) in word.as_bytes().iter().enumerate() {
+                if known[i].is_some() {
+                    continue;
+                }
+                if let Some(counts) = stats.counts.get_mut(&(b as char)) {
+                    counts[i] += 1;
+                }
+            }
+        }
+
+        stats
+    }
 }
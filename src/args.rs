@@ -0,0 +1,53 @@
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// Minimal `--name value` parser for the subcommand options. An option not
+/// followed by a value (`--name --other`, or at the end) is a flag.
+pub struct Args {
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+}
+
+impl Args {
+    pub fn parse(args: &[String]) -> Self {
+        let mut values = HashMap::new();
+        let mut flags = HashSet::new();
+
+        let mut iter = args.iter().peekable();
+        while let Some(arg) = iter.next() {
+            if let Some(name) = arg.strip_prefix("--") {
+                match iter.next_if(|next| !next.starts_with("--")) {
+                    Some(value) => {
+                        values.insert(name.to_string(), value.clone());
+                    }
+                    None => {
+                        flags.insert(name.to_string());
+                    }
+                }
+            }
+        }
+
+        Self { values, flags }
+    }
+
+    /// Whether the flag `--name` was given.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|v| v.as_str())
+    }
+
+    /// Parses the value of `--name`, returning `None` if it wasn't given.
+    pub fn parse_value<T: FromStr>(&self, name: &str) -> Result<Option<T>> {
+        match self.value(name) {
+            Some(raw) => raw
+                .parse()
+                .map(Some)
+                .map_err(|_| anyhow!("Invalid value for --{}: '{}'", name, raw)),
+            None => Ok(None),
+        }
+    }
+}
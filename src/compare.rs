@@ -0,0 +1,235 @@
+use crate::error::{Error, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// How one config did over the answers.
+#[derive(Debug, Clone)]
+pub struct Summary {
+    pub average: f64,
+    pub max_guesses: usize,
+    /// Answers needing more than six guesses.
+    pub failures: usize,
+    /// Number of answers solved in each number of guesses.
+    pub distribution: BTreeMap<usize, usize>,
+}
+
+impl Summary {
+    fn new(guesses: &BTreeMap<String, usize>) -> Self {
+        let mut distribution = BTreeMap::new();
+        for &count in guesses.values() {
+            *distribution.entry(count).or_insert(0) += 1;
+        }
+
+        let total: usize = guesses.values().sum();
+        Self {
+            average: total as f64 / guesses.len().max(1) as f64,
+            max_guesses: guesses.values().copied().max().unwrap_or(0),
+            failures: guesses.values().filter(|&&count| count > 6).count(),
+            distribution,
+        }
+    }
+}
+
+/// Paired comparison of two configs over the same answers.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub a: Summary,
+    pub b: Summary,
+    /// Answers `a` solves in fewer guesses than `b`.
+    pub a_wins: usize,
+    pub b_wins: usize,
+    pub ties: usize,
+    /// Every answer the configs disagree on, as `(answer, guesses a, guesses b)`,
+    /// largest difference first.
+    pub differences: Vec<(String, usize, usize)>,
+    /// Mean of `guesses a - guesses b`; negative means `a` is better.
+    pub mean_difference: f64,
+    /// Paired t statistic of the differences.
+    pub t: f64,
+    /// Two-sided p-value of the paired test.
+    pub p: f64,
+}
+
+/// Compares the guesses two configs need for each answer. Both must cover
+/// the same answers.
+pub fn compare(a: &BTreeMap<String, usize>, b: &BTreeMap<String, usize>) -> Result<Comparison> {
+    if a.len() != b.len() || a.keys().any(|answer| !b.contains_key(answer)) {
+        return Err(Error::Invalid("The configs were simulated over different answers".to_string()));
+    }
+    if a.is_empty() {
+        return Err(Error::Invalid("No answers to compare".to_string()));
+    }
+
+    let mut differences = Vec::new();
+    let (mut a_wins, mut b_wins) = (0, 0);
+    for (answer, &guesses_a) in a {
+        let guesses_b = b[answer];
+        if guesses_a < guesses_b {
+            a_wins += 1;
+        } else if guesses_b < guesses_a {
+            b_wins += 1;
+        }
+        if guesses_a != guesses_b {
+            differences.push((answer.clone(), guesses_a, guesses_b));
+        }
+    }
+    differences.sort_by_key(|&(_, ga, gb)| std::cmp::Reverse(ga.abs_diff(gb)));
+
+    let (mean_difference, t, p) = paired_t_test(a.keys().map(|answer| a[answer] as f64 - b[answer] as f64));
+
+    Ok(Comparison {
+        a: Summary::new(a),
+        b: Summary::new(b),
+        a_wins,
+        b_wins,
+        ties: a.len() - a_wins - b_wins,
+        differences,
+        mean_difference,
+        t,
+        p,
+    })
+}
+
+/// Mean, t statistic and two-sided p-value of paired differences. With
+/// thousands of answers the t distribution is practically normal, so the
+/// p-value uses the normal approximation.
+fn paired_t_test(differences: impl Iterator<Item = f64>) -> (f64, f64, f64) {
+    let differences: Vec<f64> = differences.collect();
+    let n = differences.len() as f64;
+    let mean = differences.iter().sum::<f64>() / n;
+    if differences.len() < 2 {
+        return (mean, 0.0, 1.0);
+    }
+
+    let variance = differences.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    if variance == 0.0 {
+        // Identical results, or a constant difference
+        return if mean == 0.0 { (mean, 0.0, 1.0) } else { (mean, f64::INFINITY.copysign(mean), 0.0) };
+    }
+
+    let t = mean / (variance / n).sqrt();
+    (mean, t, erfc(t.abs() / std::f64::consts::SQRT_2))
+}
+
+/// Complementary error function, with a relative error below 1.2e-7
+/// (Numerical Recipes' Chebyshev fit).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * poly.exp();
+    if x >= 0.0 { result } else { 2.0 - result }
+}
+
+/// Human readable report of a comparison, listing the `show` answers with the
+/// largest differences.
+pub fn report(comparison: &Comparison, name_a: &str, name_b: &str, show: usize) -> String {
+    let mut out = String::new();
+    let (a, b) = (&comparison.a, &comparison.b);
+
+    let _ = writeln!(out, "A: {}", name_a);
+    let _ = writeln!(out, "B: {}\n", name_b);
+    let _ = writeln!(out, "{:<12} {:>12} {:>12}", "", "A", "B");
+    let _ = writeln!(out, "{:<12} {:>12.4} {:>12.4}", "Average", a.average, b.average);
+    let _ = writeln!(out, "{:<12} {:>12} {:>12}", "Worst", a.max_guesses, b.max_guesses);
+    let _ = writeln!(out, "{:<12} {:>12} {:>12}", "Failures", a.failures, b.failures);
+
+    let _ = writeln!(out, "\nGuess distribution:");
+    let longest = a.max_guesses.max(b.max_guesses);
+    for guesses in 1..=longest {
+        let count = |summary: &Summary| summary.distribution.get(&guesses).copied().unwrap_or(0);
+        let _ = writeln!(out, "{:<12} {:>12} {:>12}", guesses, count(a), count(b));
+    }
+
+    let _ = writeln!(
+        out,
+        "\nPer answer: A better on {}, B better on {}, equal on {}",
+        comparison.a_wins, comparison.b_wins, comparison.ties
+    );
+    let _ = writeln!(
+        out,
+        "Mean difference (A - B): {:+.4} guesses, paired t = {:.3}, p = {:.4}",
+        comparison.mean_difference, comparison.t, comparison.p
+    );
+    let verdict = if comparison.p >= 0.05 {
+        "not significant at the 5% level".to_string()
+    } else if comparison.mean_difference < 0.0 {
+        "A is significantly better at the 5% level".to_string()
+    } else {
+        "B is significantly better at the 5% level".to_string()
+    };
+    let _ = writeln!(out, "Result: {}", verdict);
+
+    if show > 0 && !comparison.differences.is_empty() {
+        let _ = writeln!(out, "\nLargest differences:");
+        let _ = writeln!(out, "{:<12} {:>12} {:>12}", "Answer", "A", "B");
+        for (answer, guesses_a, guesses_b) in comparison.differences.iter().take(show) {
+            let _ = writeln!(out, "{:<12} {:>12} {:>12}", answer, guesses_a, guesses_b);
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(actual: f64, expected: f64) -> bool {
+        (actual - expected).abs() <= 2e-7 * expected.abs()
+    }
+
+    #[test]
+    fn erfc_known_values() {
+        let known = [
+            (0.0, 1.0),
+            (0.5, 0.4795001221869535),
+            (1.0, 0.15729920705028513),
+            (2.0, 0.004677734981047266),
+            (3.0, 2.209049699858544e-5),
+            (-1.0, 1.8427007929497148),
+        ];
+        for (x, expected) in known {
+            assert!(close(erfc(x), expected), "erfc({}) = {}, expected {}", x, erfc(x), expected);
+        }
+    }
+
+    #[test]
+    fn paired_test_by_hand() {
+        // Differences 2, 0, 1, -1, 3: mean 1, variance 10 / 4 = 2.5,
+        // t = 1 / sqrt(2.5 / 5) = sqrt(2), p = erfc(sqrt(2) / sqrt(2)) = erfc(1)
+        let (mean, t, p) = paired_t_test([2.0, 0.0, 1.0, -1.0, 3.0].into_iter());
+        assert_eq!(mean, 1.0);
+        assert!(close(t, std::f64::consts::SQRT_2));
+        assert!(close(p, 0.15729920705028513));
+
+        assert_eq!(paired_t_test([0.0, 0.0, 0.0].into_iter()), (0.0, 0.0, 1.0));
+        assert_eq!(paired_t_test([-1.0, -1.0].into_iter()), (-1.0, f64::NEG_INFINITY, 0.0));
+        assert_eq!(paired_t_test([2.0].into_iter()), (2.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn compares_per_answer() {
+        let guesses = |counts: [usize; 5]| -> BTreeMap<String, usize> {
+            ["apple", "berry", "cider", "dates", "elder"].iter().map(|w| w.to_string()).zip(counts).collect()
+        };
+        let comparison = compare(&guesses([5, 3, 4, 2, 6]), &guesses([3, 3, 3, 3, 3])).unwrap();
+
+        assert_eq!((comparison.a_wins, comparison.b_wins, comparison.ties), (1, 3, 1));
+        assert_eq!(comparison.a.average, 4.0);
+        assert_eq!(comparison.b.max_guesses, 3);
+        let answers: Vec<&str> = comparison.differences.iter().map(|(answer, _, _)| answer.as_str()).collect();
+        assert_eq!(answers, ["elder", "apple", "cider", "dates"]);
+        assert!(close(comparison.t, std::f64::consts::SQRT_2));
+
+        let mut other = guesses([3, 3, 3, 3, 3]);
+        other.remove("apple");
+        other.insert("fruit".to_string(), 3);
+        assert!(compare(&guesses([5, 3, 4, 2, 6]), &other).is_err());
+    }
+}
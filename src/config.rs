@@ -0,0 +1,352 @@
+use crate::endgame::EndgameConfig;
+use crate::lookahead::LookaheadConfig;
+use crate::ranking::RankMethod;
+use crate::error::{self, Error, Result};
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+/// Default location of the solver configuration.
+pub const CONFIG_PATH: &str = "solver_config.json";
+
+/// Version of the configuration format written by this build. Version 1 is
+/// the original bare list of `[w_pos, w_overall, w_unique]` tuples.
+pub const CONFIG_VERSION: u64 = 2;
+
+/// Validation error message naming the bad field, turned into `Error::Config`
+/// with the file name by `SolverConfig::parse`.
+type FieldResult<T> = std::result::Result<T, String>;
+
+/// Everything the solver can be configured with in `solver_config.json`.
+#[derive(Debug, Clone)]
+pub struct SolverConfig {
+    pub strategy: RankMethod,
+    /// `(w_pos, w_overall, w_unique)` for every turn; the last one is reused
+    /// for later turns. Never empty.
+    pub weights: Vec<(f64, f64, f64)>,
+    /// Only suggest guesses that can still be the answer.
+    pub hard_mode: bool,
+    /// Number of suggestions the solver shows.
+    pub top_n: usize,
+    /// Recompute the letter statistics from the remaining words every turn.
+    pub live_stats: bool,
+    pub endgame: EndgameConfig,
+    pub lookahead: LookaheadConfig,
+}
+
+impl Default for SolverConfig {
+    fn default() -> Self {
+        Self {
+            strategy: RankMethod::Weighted,
+            weights: vec![
+                (0.1, 0.2, 0.7),
+                (0.15, 0.25, 0.6),
+                (0.25, 0.35, 0.4),
+                (0.35, 0.45, 0.2),
+                (0.45, 0.45, 0.1),
+                (0.6, 0.35, 0.05),
+            ],
+            hard_mode: false,
+            top_n: 10,
+            live_stats: false,
+            endgame: EndgameConfig::default(),
+            lookahead: LookaheadConfig::default(),
+        }
+    }
+}
+
+impl SolverConfig {
+    /// Parses and validates a config of any supported version. `source`
+    /// names the file in error messages.
+    pub fn parse(content: &str, source: &str) -> Result<Self> {
+        let value: Value = error::parse_json(content, source)?;
+
+        let config = match &value {
+            Value::Array(turns) => Self::from_v1(turns),
+            Value::Object(fields) => Self::from_v2(fields),
+            _ => Err("expected a JSON object (or the old list of weights)".to_string()),
+        };
+        config.map_err(|message| Error::Config { path: source.to_string(), message })
+    }
+
+    /// Whether the file still uses the version 1 tuple list.
+    pub fn is_v1(content: &str) -> bool {
+        matches!(serde_json::from_str::<Value>(content), Ok(Value::Array(_)))
+    }
+
+    fn from_v1(turns: &[Value]) -> FieldResult<Self> {
+        Ok(Self {
+            weights: parse_weights(turns, "")?,
+            ..Self::default()
+        })
+    }
+
+    fn from_v2(fields: &Map<String, Value>) -> FieldResult<Self> {
+        const KNOWN: [&str; 8] =
+            ["version", "strategy", "weights", "hard_mode", "top_n", "live_stats", "endgame", "lookahead"];
+        check_known(fields, &KNOWN, "")?;
+
+        let version = fields
+            .get("version")
+            .ok_or_else(|| format!("missing field `version` (current version is {})", CONFIG_VERSION))?;
+        match version.as_u64() {
+            Some(CONFIG_VERSION) => {}
+            Some(v) if v > CONFIG_VERSION => {
+                return Err(format!("`version` {} is newer than this build supports ({})", v, CONFIG_VERSION));
+            }
+            _ => return Err(format!("`version` must be {}, got {}", CONFIG_VERSION, version)),
+        }
+
+        let mut config = Self::default();
+
+        if let Some(value) = fields.get("strategy") {
+            config.strategy = parse_str(value, "strategy")?;
+        }
+        match fields.get("weights") {
+            Some(Value::Array(turns)) => config.weights = parse_weights(turns, "weights")?,
+            Some(other) => return Err(format!("`weights` must be a list, got {}", other)),
+            None => return Err("missing field `weights`".to_string()),
+        }
+        if let Some(value) = fields.get("hard_mode") {
+            config.hard_mode = parse_bool(value, "hard_mode")?;
+        }
+        if let Some(value) = fields.get("top_n") {
+            config.top_n = parse_count(value, "top_n")? as usize;
+            if config.top_n == 0 {
+                return Err("`top_n` must be at least 1".to_string());
+            }
+        }
+        if let Some(value) = fields.get("live_stats") {
+            config.live_stats = parse_bool(value, "live_stats")?;
+        }
+
+        if let Some(value) = fields.get("endgame") {
+            let endgame = parse_object(value, "endgame")?;
+            check_known(endgame, &["threshold", "objective"], "endgame.")?;
+            if let Some(value) = endgame.get("threshold") {
+                config.endgame.threshold = parse_count(value, "endgame.threshold")? as usize;
+            }
+            if let Some(value) = endgame.get("objective") {
+                config.endgame.objective = parse_str(value, "endgame.objective")?;
+            }
+        }
+
+        if let Some(value) = fields.get("lookahead") {
+            let lookahead = parse_object(value, "lookahead")?;
+            check_known(lookahead, &["base", "top_k", "max_nodes", "time_limit_ms"], "lookahead.")?;
+            if let Some(value) = lookahead.get("base") {
+                config.lookahead.base = parse_str(value, "lookahead.base")?;
+                if config.lookahead.base == RankMethod::Lookahead {
+                    return Err("`lookahead.base` can't be lookahead itself".to_string());
+                }
+            }
+            if let Some(value) = lookahead.get("top_k") {
+                config.lookahead.top_k = parse_count(value, "lookahead.top_k")? as usize;
+            }
+            if let Some(value) = lookahead.get("max_nodes") {
+                config.lookahead.max_nodes = parse_count(value, "lookahead.max_nodes")?;
+            }
+            match lookahead.get("time_limit_ms") {
+                None | Some(Value::Null) => {}
+                Some(value) => {
+                    let ms = parse_count(value, "lookahead.time_limit_ms")?;
+                    config.lookahead.time_limit = Some(Duration::from_millis(ms));
+                }
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// The config in the current format, with one line per turn of weights.
+    pub fn to_json(&self) -> String {
+        let weights: Vec<String> = self
+            .weights
+            .iter()
+            .map(|(pos, overall, unique)| {
+                format!("    {{ \"pos\": {}, \"overall\": {}, \"unique\": {} }}", pos, overall, unique)
+            })
+            .collect();
+        let time_limit = self
+            .lookahead
+            .time_limit
+            .map_or("null".to_string(), |limit| limit.as_millis().to_string());
+
+        format!(
+            "{{\n  \"version\": {},\n  \"strategy\": \"{}\",\n  \"hard_mode\": {},\n  \"top_n\": {},\n  \"live_stats\": {},\n  \"weights\": [\n{}\n  ],\n  \"endgame\": {{ \"threshold\": {}, \"objective\": \"{}\" }},\n  \"lookahead\": {{ \"base\": \"{}\", \"top_k\": {}, \"max_nodes\": {}, \"time_limit_ms\": {} }}\n}}\n",
+            CONFIG_VERSION,
+            self.strategy.name(),
+            self.hard_mode,
+            self.top_n,
+            self.live_stats,
+            weights.join(",\n"),
+            self.endgame.threshold,
+            self.endgame.objective.name(),
+            self.lookahead.base.name(),
+            self.lookahead.top_k,
+            self.lookahead.max_nodes,
+            time_limit,
+        )
+    }
+}
+
+/// Weights as `[w_pos, w_overall, w_unique]` lists (version 1) or
+/// `{ "pos", "overall", "unique" }` objects. `field` prefixes error locations.
+fn parse_weights(turns: &[Value], field: &str) -> FieldResult<Vec<(f64, f64, f64)>> {
+    if turns.is_empty() {
+        return Err(format!("`{}` needs weights for at least one turn", if field.is_empty() { "weights" } else { field }));
+    }
+
+    turns
+        .iter()
+        .enumerate()
+        .map(|(turn, value)| {
+            let location = format!("{}[{}]", field, turn);
+            let weights = match value {
+                Value::Array(parts) if parts.len() == 3 => (
+                    parse_weight(&parts[0], &format!("{}[0]", location))?,
+                    parse_weight(&parts[1], &format!("{}[1]", location))?,
+                    parse_weight(&parts[2], &format!("{}[2]", location))?,
+                ),
+                Value::Object(parts) => {
+                    let prefix = format!("{}.", location);
+                    check_known(parts, &["pos", "overall", "unique"], &prefix)?;
+                    let get = |name: &str| {
+                        let location = format!("{}{}", prefix, name);
+                        parts
+                            .get(name)
+                            .ok_or_else(|| format!("missing field `{}`", location))
+                            .and_then(|value| parse_weight(value, &location))
+                    };
+                    (get("pos")?, get("overall")?, get("unique")?)
+                }
+                other => {
+                    return Err(format!("`{}` must be [w_pos, w_overall, w_unique], got {}", location, other));
+                }
+            };
+            Ok(weights)
+        })
+        .collect()
+}
+
+fn parse_weight(value: &Value, field: &str) -> FieldResult<f64> {
+    value
+        .as_f64()
+        .filter(|w| w.is_finite() && *w >= 0.0)
+        .ok_or_else(|| format!("`{}` must be a number of at least 0, got {}", field, value))
+}
+
+fn parse_count(value: &Value, field: &str) -> FieldResult<u64> {
+    value
+        .as_u64()
+        .ok_or_else(|| format!("`{}` must be a whole number of at least 0, got {}", field, value))
+}
+
+fn parse_bool(value: &Value, field: &str) -> FieldResult<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| format!("`{}` must be true or false, got {}", field, value))
+}
+
+fn parse_str<T: std::str::FromStr<Err = Error>>(value: &Value, field: &str) -> FieldResult<T> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| format!("`{}` must be a string, got {}", field, value))?;
+    text.parse().map_err(|e| format!("`{}`: {}", field, e))
+}
+
+fn parse_object<'v>(value: &'v Value, field: &str) -> FieldResult<&'v Map<String, Value>> {
+    value
+        .as_object()
+        .ok_or_else(|| format!("`{}` must be an object, got {}", field, value))
+}
+
+/// Rejects unknown fields, which are most likely typos.
+fn check_known(fields: &Map<String, Value>, known: &[&str], prefix: &str) -> FieldResult<()> {
+    match fields.keys().find(|key| !known.contains(&key.as_str())) {
+        Some(key) => Err(format!("unknown field `{}{}` (expected one of: {})", prefix, key, known.join(", "))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The message of an `Error::Config` for `content`.
+    fn error(content: &str) -> String {
+        match SolverConfig::parse(content, "test.json") {
+            Err(Error::Config { path, message }) => {
+                assert_eq!(path, "test.json");
+                message
+            }
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn migrates_v1() {
+        let v1 = "[[0.1, 0.2, 0.7], [0.5, 0.5, 0]]";
+        assert!(SolverConfig::is_v1(v1));
+
+        let config = SolverConfig::parse(v1, "test.json").unwrap();
+        assert_eq!(config.weights, vec![(0.1, 0.2, 0.7), (0.5, 0.5, 0.0)]);
+        assert_eq!(config.top_n, SolverConfig::default().top_n);
+
+        let v2 = config.to_json();
+        assert!(!SolverConfig::is_v1(&v2));
+        let migrated = SolverConfig::parse(&v2, "test.json").unwrap();
+        assert_eq!(migrated.weights, config.weights);
+        assert_eq!(migrated.strategy, config.strategy);
+        assert_eq!(migrated.top_n, config.top_n);
+        assert_eq!(migrated.endgame.threshold, config.endgame.threshold);
+        assert_eq!(migrated.lookahead.max_nodes, config.lookahead.max_nodes);
+    }
+
+    #[test]
+    fn current_format_round_trips() {
+        let mut config = SolverConfig {
+            hard_mode: true,
+            top_n: 3,
+            ..SolverConfig::default()
+        };
+        config.lookahead.time_limit = Some(Duration::from_millis(250));
+
+        let parsed = SolverConfig::parse(&config.to_json(), "test.json").unwrap();
+        assert_eq!(parsed.weights, config.weights);
+        assert!(parsed.hard_mode);
+        assert_eq!(parsed.top_n, 3);
+        assert_eq!(parsed.lookahead.time_limit, Some(Duration::from_millis(250)));
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let message = error(r#"{ "version": 2, "weights": [[0, 0, 1]], "top": 5 }"#);
+        assert!(message.starts_with("unknown field `top`"), "{}", message);
+
+        let message = error(r#"{ "version": 2, "weights": [[0, 0, 1]], "endgame": { "limit": 5 } }"#);
+        assert!(message.starts_with("unknown field `endgame.limit`"), "{}", message);
+
+        let message = error(r#"{ "version": 2, "weights": [{ "pos": 0, "overall": 0, "unique": 1, "extra": 0 }] }"#);
+        assert!(message.starts_with("unknown field `weights[0].extra`"), "{}", message);
+    }
+
+    #[test]
+    fn errors_name_the_field() {
+        let message = error(
+            r#"{ "version": 2, "weights": [
+                { "pos": 0, "overall": 0, "unique": 1 },
+                { "pos": 0, "overall": 0, "unique": 1 },
+                { "pos": -1, "overall": 0, "unique": 1 }
+            ] }"#,
+        );
+        assert_eq!(message, "`weights[2].pos` must be a number of at least 0, got -1");
+
+        assert_eq!(
+            error(r#"{ "version": 2, "weights": [{ "pos": 0, "unique": 1 }] }"#),
+            "missing field `weights[0].overall`"
+        );
+        assert_eq!(error("[[0, 0, 1], [0, \"x\", 1]]"), "`[1][1]` must be a number of at least 0, got \"x\"");
+        assert_eq!(error(r#"{ "version": 2, "weights": [[0, 0, 1]], "top_n": 0 }"#), "`top_n` must be at least 1");
+        assert_eq!(error(r#"{ "weights": [[0, 0, 1]] }"#), "missing field `version` (current version is 2)");
+    }
+}
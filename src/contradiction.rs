@@ -0,0 +1,266 @@
+use crate::game::{CellState, LineData, Pattern};
+use crate::patterns::pattern_code;
+use crate::ranking::PATTERN_COUNT;
+use crate::wordset::WordSet;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A line of feedback that no word in the word list could have produced
+/// together with the lines before it.
+#[derive(Debug, Clone)]
+pub struct Contradiction {
+    /// Index of the line that introduced the contradiction.
+    pub line: usize,
+    /// The cell of that line conflicting with an earlier line, if a simple
+    /// rule finds one.
+    pub cell: Option<usize>,
+    /// Why the line can't be right, e.g. "line 1 showed there is no 'e' in the word".
+    pub reason: String,
+    /// Changes of a single cell that make the lines consistent again.
+    pub fixes: Vec<Fix>,
+}
+
+/// One cell of the contradicting line changed to another state.
+#[derive(Debug, Clone, Copy)]
+pub struct Fix {
+    pub cell: usize,
+    pub state: CellState,
+    /// The line's whole pattern with the change.
+    pub pattern: Pattern,
+    /// Number of words matching every line after the change.
+    pub matches: usize,
+}
+
+impl fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cell {
+            Some(cell) => write!(f, "Line {}, cell {}: {}", self.line + 1, cell + 1, self.reason),
+            None => write!(f, "Line {}: {}", self.line + 1, self.reason),
+        }
+    }
+}
+
+/// Checks the last line of `lines` against the earlier ones, except the
+/// lines in `kept` that were accepted despite contradicting. Returns `None`
+/// while some word of `words` still matches them all.
+pub fn find(lines: &[LineData], kept: &[usize], words: &WordSet) -> Option<Contradiction> {
+    let line = lines.last()?;
+    let index = lines.len() - 1;
+    let earlier: Vec<&LineData> = checked(&lines[..index], kept).map(|(_, line)| line).collect();
+
+    let consistent: Vec<usize> = (0..words.len())
+        .filter(|&w| earlier.iter().all(|l| pattern_code(l.word.as_bytes(), words.word(w)) == l.pattern().code()))
+        .collect();
+
+    let code = line.pattern().code();
+    if consistent.iter().any(|&w| pattern_code(line.word.as_bytes(), words.word(w)) == code) {
+        return None;
+    }
+    Some(describe(lines, kept, &consistent, words))
+}
+
+/// The lines with their indices, without the kept ones.
+fn checked<'l>(lines: &'l [LineData], kept: &'l [usize]) -> impl Iterator<Item = (usize, &'l LineData)> {
+    lines.iter().enumerate().filter(|(i, _)| !kept.contains(i))
+}
+
+/// Builds the contradiction for the last line, given the words matching the
+/// checked lines before it.
+fn describe(lines: &[LineData], kept: &[usize], consistent: &[usize], words: &WordSet) -> Contradiction {
+    let index = lines.len() - 1;
+    let line = &lines[index];
+    let pattern = line.pattern();
+
+    // How many of the consistent words give each pattern for this guess
+    let mut counts = [0usize; PATTERN_COUNT];
+    for &w in consistent {
+        counts[pattern_code(line.word.as_bytes(), words.word(w)) as usize] += 1;
+    }
+
+    let mut fixes = Vec::new();
+    for cell in 0..5 {
+        for state in [CellState::Wrong, CellState::Misplaced, CellState::Correct] {
+            if state == pattern.states()[cell] {
+                continue;
+            }
+            let mut states = *pattern.states();
+            states[cell] = state;
+            let fixed = Pattern::new(states);
+            let matches = counts[fixed.code() as usize];
+            if matches > 0 {
+                fixes.push(Fix { cell, state, pattern: fixed, matches });
+            }
+        }
+    }
+    fixes.sort_by_key(|fix| std::cmp::Reverse(fix.matches));
+
+    let (cell, reason) = match conflict(&lines[..index], kept, line) {
+        Some((cell, reason)) => (Some(cell), reason),
+        None if checked(&lines[..index], kept).next().is_none() => (None, "no word in the word list gives this pattern".to_string()),
+        None => (None, "no word in the word list gives this pattern together with the lines before".to_string()),
+    };
+
+    Contradiction { line: index, cell, reason, fixes }
+}
+
+/// Checks every cell of `line` against what the earlier lines (except the
+/// kept ones) showed, and returns the first conflicting cell with the reason.
+fn conflict(earlier: &[LineData], kept: &[usize], line: &LineData) -> Option<(usize, String)> {
+    // What the earlier lines showed, with the number of the first line showing it
+    let mut correct: [Option<(char, usize)>; 5] = [None; 5];
+    let mut misplaced: HashMap<(usize, char), usize> = HashMap::new();
+    let mut present: HashMap<char, usize> = HashMap::new();
+    let mut absent: HashMap<char, usize> = HashMap::new();
+
+    for (number, earlier_line) in checked(earlier, kept).map(|(i, l)| (i + 1, l)) {
+        for (i, cell) in earlier_line.cells.iter().enumerate() {
+            match cell.state {
+                CellState::Correct => {
+                    correct[i].get_or_insert((cell.letter, number));
+                    present.entry(cell.letter).or_insert(number);
+                }
+                CellState::Misplaced => {
+                    misplaced.entry((i, cell.letter)).or_insert(number);
+                    present.entry(cell.letter).or_insert(number);
+                }
+                CellState::Wrong if !shown_present(earlier_line, cell.letter) => {
+                    absent.entry(cell.letter).or_insert(number);
+                }
+                CellState::Wrong => {}
+            }
+        }
+    }
+
+    for (i, cell) in line.cells.iter().enumerate() {
+        let letter = cell.letter;
+        let correct_here = correct[i];
+        let reason = match cell.state {
+            CellState::Correct => match correct_here {
+                Some((other, number)) if other != letter => {
+                    Some(format!("'{}' can't be correct here, line {} marked '{}' correct at this position", letter, number, other))
+                }
+                _ => misplaced
+                    .get(&(i, letter))
+                    .map(|number| format!("'{}' can't be correct here, line {} marked it misplaced at this position", letter, number))
+                    .or_else(|| absent.get(&letter).map(|number| {
+                        format!("'{}' can't be correct, line {} showed there is no '{}' in the word", letter, number, letter)
+                    })),
+            },
+            CellState::Misplaced => match correct_here {
+                Some((other, number)) if other == letter => {
+                    Some(format!("'{}' can't be misplaced here, line {} marked it correct at this position", letter, number))
+                }
+                _ => absent.get(&letter).map(|number| {
+                    format!("'{}' can't be misplaced, line {} showed there is no '{}' in the word", letter, number, letter)
+                }),
+            },
+            CellState::Wrong => match correct_here {
+                Some((other, number)) if other == letter => {
+                    Some(format!("'{}' can't be wrong here, line {} marked it correct at this position", letter, number))
+                }
+                _ if !shown_present(line, letter) => present.get(&letter).map(|number| {
+                    format!("'{}' can't be absent, line {} showed it is in the word", letter, number)
+                }),
+                _ => None,
+            },
+        };
+
+        if let Some(reason) = reason {
+            return Some((i, reason));
+        }
+    }
+
+    None
+}
+
+/// Whether the line marks `letter` correct or misplaced in any cell.
+fn shown_present(line: &LineData, letter: char) -> bool {
+    line.cells
+        .iter()
+        .any(|cell| cell.letter == letter && cell.state != CellState::Wrong)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::GameData;
+
+    const WORDS: [&str; 5] = ["crane", "crate", "grate", "prate", "trace"];
+
+    fn words() -> WordSet {
+        WordSet::new(&WORDS.map(String::from)).unwrap()
+    }
+
+    fn lines(history: &[(&str, &str)]) -> Vec<LineData> {
+        let mut game = GameData::new();
+        for (word, pattern) in history {
+            game.add_line(word, &pattern.parse().unwrap()).unwrap();
+        }
+        game.lines
+    }
+
+    #[test]
+    fn consistent_lines() {
+        // slate:wwccc leaves crate, grate and prate; trace gives mccmc for crate
+        let lines = lines(&[("slate", "wwccc"), ("trace", "mccmc")]);
+        assert!(find(&lines, &[], &words()).is_none());
+    }
+
+    #[test]
+    fn mistyped_cell() {
+        // The 'c' of trace was entered as correct where line 1 showed the 't'
+        let lines = lines(&[("slate", "wwccc"), ("trace", "mcccc")]);
+        let contradiction = find(&lines, &[], &words()).unwrap();
+
+        assert_eq!(contradiction.line, 1);
+        assert_eq!(contradiction.cell, Some(3));
+        assert_eq!(
+            contradiction.to_string(),
+            "Line 2, cell 4: 'c' can't be correct here, line 1 marked 't' correct at this position"
+        );
+
+        // grate and prate give mccwc, only crate gives mccmc
+        let fixes: Vec<(usize, CellState, String, usize)> = contradiction
+            .fixes
+            .iter()
+            .map(|fix| (fix.cell, fix.state, fix.pattern.to_string(), fix.matches))
+            .collect();
+        assert_eq!(
+            fixes,
+            vec![
+                (3, CellState::Wrong, "mccwc".to_string(), 2),
+                (3, CellState::Misplaced, "mccmc".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn letter_marked_correct() {
+        let lines = lines(&[("slate", "wwccc"), ("trace", "mccmw")]);
+        let contradiction = find(&lines, &[], &words()).unwrap();
+
+        assert_eq!(contradiction.cell, Some(4));
+        assert_eq!(contradiction.reason, "'e' can't be wrong here, line 1 marked it correct at this position");
+        assert_eq!(contradiction.fixes.len(), 1);
+        assert_eq!(contradiction.fixes[0].pattern.to_string(), "mccmc");
+        assert_eq!(contradiction.fixes[0].matches, 1);
+    }
+
+    #[test]
+    fn kept_lines_are_not_checked() {
+        // crane:ccccc contradicts slate:wwccc; once kept, trace is checked against slate alone
+        let history = lines(&[("slate", "wwccc"), ("crane", "ccccc"), ("trace", "mccmc")]);
+        assert!(find(&history, &[1], &words()).is_none());
+
+        let contradiction = find(&history, &[], &words()).unwrap();
+        assert_eq!(contradiction.line, 2);
+        assert_eq!(contradiction.cell, None);
+        assert_eq!(contradiction.reason, "no word in the word list gives this pattern together with the lines before");
+        assert!(contradiction.fixes.is_empty());
+
+        // With every earlier line kept, only the word list is left to check against
+        let history = lines(&[("crane", "ccccc"), ("trace", "mcccc")]);
+        let contradiction = find(&history, &[0], &words()).unwrap();
+        assert_eq!(contradiction.reason, "no word in the word list gives this pattern");
+    }
+}
@@ -0,0 +1,430 @@
+use crate::patterns::SOLVED_CODE;
+use crate::error::{Error, Result};
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+/// What the exact endgame search minimises.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    /// Average number of guesses over the remaining candidates.
+    Expected,
+    /// Number of guesses needed for the hardest remaining candidate.
+    WorstCase,
+}
+
+impl FromStr for Objective {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "expected" => Ok(Self::Expected),
+            "worst" => Ok(Self::WorstCase),
+            _ => Err(Error::UnknownName { kind: "endgame objective", name: s.to_string(), expected: "expected or worst" }),
+        }
+    }
+}
+
+impl Objective {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Expected => "expected",
+            Self::WorstCase => "worst",
+        }
+    }
+
+    /// What the endgame scores measure, for display.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Expected => "expected guesses",
+            Self::WorstCase => "worst-case guesses",
+        }
+    }
+}
+
+/// When the solver switches to the exact endgame search.
+#[derive(Debug, Clone, Copy)]
+pub struct EndgameConfig {
+    /// Search exhaustively once at most this many candidates are left (0 disables it).
+    pub threshold: usize,
+    pub objective: Objective,
+}
+
+impl Default for EndgameConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 12,
+            objective: Objective::Expected,
+        }
+    }
+}
+
+impl EndgameConfig {
+    pub fn applies(&self, candidates: usize) -> bool {
+        candidates > 0 && candidates <= self.threshold
+    }
+}
+
+/// Exhaustive search for the guess that finishes a small candidate set in the
+/// fewest guesses. Candidate sets are memoised, and guesses that split the
+/// candidates identically are only searched once.
+///
+/// `pattern(g, c)` returns the pattern code of guess `g` against candidate `c`;
+/// candidate indices are whatever universe the caller uses.
+pub struct Endgame<P> {
+    guesses: usize,
+    objective: Objective,
+    pattern: P,
+    /// Optimal cost of a candidate set and the guess achieving it.
+    memo: HashMap<Vec<usize>, (u32, usize)>,
+    /// Only try this many of the most promising splits per set, if limited.
+    breadth: Option<usize>,
+}
+
+/// The optimal way to play out a candidate set: the guess, and for every
+/// pattern it can leave (except `ccccc`) how to continue.
+#[derive(Debug, Clone)]
+pub struct Plan {
+    pub guess: usize,
+    /// Whether the guess is one of the candidates.
+    pub solves: bool,
+    pub children: Vec<(u8, Plan)>,
+}
+
+/// One way of splitting the candidates, with the first guess producing it.
+/// The buckets themselves are only built once the split survives pruning.
+struct Split {
+    guess: usize,
+    solves: bool,
+    /// Pattern code of every candidate, in candidate order.
+    codes: Vec<u8>,
+    /// Sizes of the unsolved buckets.
+    sizes: Vec<usize>,
+}
+
+impl Split {
+    fn new(guess: usize, codes: Vec<u8>) -> Self {
+        // Bucket sizes from the sorted codes, sets are small
+        let mut sorted = codes.clone();
+        sorted.sort_unstable();
+        let solves = sorted.last() == Some(&SOLVED_CODE);
+        let sizes: Vec<usize> = sorted
+            .chunk_by(|a, b| a == b)
+            .filter(|run| run[0] != SOLVED_CODE)
+            .map(|run| run.len())
+            .collect();
+
+        Self { guess, solves, codes, sizes }
+    }
+
+    fn buckets(&self, candidates: &[usize]) -> Vec<Vec<usize>> {
+        let mut buckets: HashMap<u8, Vec<usize>> = HashMap::new();
+        for (&code, &candidate) in self.codes.iter().zip(candidates) {
+            if code != SOLVED_CODE {
+                buckets.entry(code).or_default().push(candidate);
+            }
+        }
+
+        let mut buckets: Vec<Vec<usize>> = buckets.into_values().collect();
+        buckets.sort();
+        buckets
+    }
+}
+
+impl<P: Fn(usize, usize) -> u8> Endgame<P> {
+    pub fn new(guesses: usize, objective: Objective, pattern: P) -> Self {
+        Self {
+            guesses,
+            objective,
+            pattern,
+            memo: HashMap::new(),
+            breadth: None,
+        }
+    }
+
+    /// Limits the search to the `breadth` most promising splits of every set.
+    /// The result is then only an upper bound of the optimum.
+    pub fn set_breadth(&mut self, breadth: Option<usize>) {
+        self.breadth = breadth;
+    }
+
+    /// Every guess that splits the candidates differently, with its exact score
+    /// (expected guesses, or worst-case guesses) when played optimally after it,
+    /// best first. Ties prefer guesses that can be the answer.
+    pub fn rank(&mut self, candidates: &[usize]) -> Vec<(usize, f64)> {
+        let n = candidates.len();
+        let all: Vec<usize> = (0..self.guesses).collect();
+        let splits = self.splits(candidates, &all);
+        let pool = pool_of(&splits);
+
+        let mut scored: Vec<(u32, bool, usize)> = splits
+            .iter()
+            .map(|split| (self.split_cost(candidates, split, &pool, u32::MAX), !split.solves, split.guess))
+            .collect();
+        scored.sort();
+
+        scored
+            .into_iter()
+            .map(|(cost, _, guess)| (guess, self.score(cost, n)))
+            .collect()
+    }
+
+    /// The first entry of `rank`, found with pruning instead of scoring every guess exactly.
+    pub fn best(&mut self, candidates: &[usize]) -> Option<(usize, f64)> {
+        let all: Vec<usize> = (0..self.guesses).collect();
+        let splits = self.splits(candidates, &all);
+        let pool = pool_of(&splits);
+
+        let mut best: Option<(u32, bool, usize)> = None;
+        for split in &splits {
+            // One above the best so far, so ties are still scored exactly
+            let bound = best.map_or(u32::MAX, |(cost, _, _)| cost + 1);
+            let key = (self.split_cost(candidates, split, &pool, bound), !split.solves, split.guess);
+            if best.is_none_or(|best| key < best) {
+                best = Some(key);
+            }
+        }
+
+        best.map(|(cost, _, guess)| (guess, self.score(cost, candidates.len())))
+    }
+
+    fn score(&self, cost: u32, n: usize) -> f64 {
+        match self.objective {
+            Objective::Expected => cost as f64 / n as f64,
+            Objective::WorstCase => cost as f64,
+        }
+    }
+
+    /// Optimal cost of the candidates: the total number of guesses over all of
+    /// them for `Expected`, the largest number for `WorstCase`.
+    ///
+    /// Only guesses from `pool` are tried. Two guesses splitting a set the same
+    /// way also split every subset of it the same way, so the pool only needs
+    /// one guess per distinct split of an enclosing set.
+    fn cost(&mut self, candidates: &[usize], pool: &[usize]) -> u32 {
+        let n = candidates.len();
+        if n <= 2 {
+            // Guess one of them, then the other if needed: always optimal
+            return self.lower_bound(n);
+        }
+        if let Some(&(cost, _)) = self.memo.get(candidates) {
+            return cost;
+        }
+
+        // No strategy can do better than guessing one candidate and then
+        // knowing the answer for each of the others.
+        let floor = self.lower_bound(n);
+
+        let splits = self.splits(candidates, pool);
+        let pool = pool_of(&splits);
+
+        let mut best = (u32::MAX, splits[0].guess);
+        for split in splits.iter().take(self.breadth.unwrap_or(usize::MAX)) {
+            let cost = self.split_cost(candidates, split, &pool, best.0);
+            if cost < best.0 {
+                best = (cost, split.guess);
+            }
+            if best.0 == floor {
+                break;
+            }
+        }
+
+        self.memo.insert(candidates.to_vec(), best);
+        best.0
+    }
+
+    /// Optimal cost of the candidates and the plan achieving it.
+    pub fn solve(&mut self, candidates: &[usize]) -> (u32, Plan) {
+        let all: Vec<usize> = (0..self.guesses).collect();
+        let cost = self.cost(candidates, &all);
+        (cost, self.plan(candidates, &all))
+    }
+
+    /// Rebuilds the plan for a set whose cost is known (memoised or trivial).
+    fn plan(&mut self, candidates: &[usize], pool: &[usize]) -> Plan {
+        let splits = self.splits(candidates, pool);
+        let child_pool = pool_of(&splits);
+
+        // Sets of one or two are always solved by the first split, which
+        // guesses a candidate; larger sets look the optimal guess up.
+        let split = if candidates.len() <= 2 {
+            &splits[0]
+        } else {
+            self.cost(candidates, pool);
+            let (_, guess) = self.memo[candidates];
+            splits.iter().find(|split| split.guess == guess).expect("memoised guess is in the pool")
+        };
+
+        self.plan_split(candidates, split, &child_pool)
+    }
+
+    fn plan_split(&mut self, candidates: &[usize], split: &Split, pool: &[usize]) -> Plan {
+        let mut children = Vec::new();
+        for bucket in split.buckets(candidates) {
+            let code = (self.pattern)(split.guess, bucket[0]);
+            children.push((code, self.plan(&bucket, pool)));
+        }
+
+        Plan {
+            guess: split.guess,
+            solves: split.solves,
+            children,
+        }
+    }
+
+    /// Cost of playing the split's guess first. Returns `bound` or more as soon
+    /// as it is clear the split can't beat it.
+    fn split_cost(&mut self, candidates: &[usize], split: &Split, pool: &[usize], bound: u32) -> u32 {
+        let n = candidates.len();
+        match self.objective {
+            Objective::Expected => {
+                let optimistic: u32 = n as u32 + split.sizes.iter().map(|&m| self.lower_bound(m)).sum::<u32>();
+                if optimistic >= bound {
+                    return optimistic;
+                }
+
+                let mut total = n as u32;
+                for bucket in &split.buckets(candidates) {
+                    total += self.cost(bucket, pool);
+                    if total >= bound {
+                        break;
+                    }
+                }
+                total
+            }
+            Objective::WorstCase => {
+                let optimistic = 1 + split.sizes.iter().map(|&m| self.lower_bound(m)).max().unwrap_or(0);
+                if optimistic >= bound {
+                    return optimistic;
+                }
+
+                let mut worst = 1;
+                for bucket in &split.buckets(candidates) {
+                    worst = worst.max(1 + self.cost(bucket, pool));
+                    if worst >= bound {
+                        break;
+                    }
+                }
+                worst
+            }
+        }
+    }
+
+    fn lower_bound(&self, n: usize) -> u32 {
+        match (self.objective, n) {
+            (_, 0) => 0,
+            (_, 1) => 1,
+            (Objective::Expected, n) => 2 * n as u32 - 1,
+            (Objective::WorstCase, _) => 2,
+        }
+    }
+
+    /// All distinct splits of the candidates by a guess, skipping guesses that
+    /// leave them all together, most promising (smallest buckets) first.
+    fn splits(&self, candidates: &[usize], pool: &[usize]) -> Vec<Split> {
+        let mut seen: HashSet<Vec<u8>> = HashSet::new();
+        let mut splits = Vec::new();
+        let mut codes: Vec<u8> = Vec::with_capacity(candidates.len());
+
+        for &guess in pool {
+            codes.clear();
+            codes.extend(candidates.iter().map(|&c| (self.pattern)(guess, c)));
+            if seen.contains(codes.as_slice()) {
+                continue;
+            }
+            seen.insert(codes.clone());
+
+            let split = Split::new(guess, codes.clone());
+            if !split.solves && split.sizes.len() == 1 {
+                continue;
+            }
+            splits.push(split);
+        }
+
+        splits.sort_by_key(|split| {
+            let sum_sq: usize = split.sizes.iter().map(|m| m * m).sum();
+            (sum_sq, !split.solves, split.guess)
+        });
+        splits
+    }
+}
+
+/// The guesses of `splits`, in index order.
+fn pool_of(splits: &[Split]) -> Vec<usize> {
+    let mut pool: Vec<usize> = splits.iter().map(|split| split.guess).collect();
+    pool.sort_unstable();
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::pattern_code;
+
+    /// Four answers only the first letter tells apart, a guess separating all
+    /// of them, and a second guess splitting them the same way.
+    const WORDS: [&str; 6] = ["batch", "catch", "hatch", "match", "cbmxx", "cbmyy"];
+    const ANSWERS: [usize; 4] = [0, 1, 2, 3];
+    const SEPARATOR: usize = 4;
+
+    fn endgame(objective: Objective) -> Endgame<impl Fn(usize, usize) -> u8> {
+        Endgame::new(WORDS.len(), objective, |g, c| pattern_code(WORDS[g].as_bytes(), WORDS[c].as_bytes()))
+    }
+
+    #[test]
+    fn trivial_sets() {
+        let mut search = endgame(Objective::Expected);
+        assert_eq!(search.solve(&[1]).0, 1);
+        assert_eq!(search.rank(&[1])[0], (1, 1.0));
+
+        // Guess one, then the other if needed: 1.5 on average
+        assert_eq!(search.solve(&[0, 1]).0, 3);
+        assert_eq!(search.best(&[0, 1]), Some((0, 1.5)));
+    }
+
+    #[test]
+    fn separating_guess_beats_candidates() {
+        let mut search = endgame(Objective::Expected);
+        let (cost, plan) = search.solve(&ANSWERS);
+
+        // The separator, then the answer: 2 guesses each. A candidate first
+        // solves one and leaves three that need the separator: 10 in total.
+        assert_eq!(cost, 8);
+        assert_eq!(plan.guess, SEPARATOR);
+        assert!(!plan.solves);
+        assert_eq!(plan.children.len(), 4);
+        assert!(plan.children.iter().all(|(_, child)| child.solves && child.children.is_empty()));
+        assert_eq!(search.memo[ANSWERS.as_slice()], (8, SEPARATOR));
+
+        let ranked = search.rank(&ANSWERS);
+        assert_eq!(ranked[0], (SEPARATOR, 2.0));
+        assert_eq!(ranked[1], (0, 2.5));
+    }
+
+    #[test]
+    fn identical_splits_are_searched_once() {
+        let mut search = endgame(Objective::Expected);
+        let ranked: Vec<usize> = search.rank(&ANSWERS).into_iter().map(|(guess, _)| guess).collect();
+
+        assert!(ranked.contains(&SEPARATOR));
+        assert!(!ranked.contains(&5));
+        assert_eq!(ranked.len(), 5);
+    }
+
+    #[test]
+    fn worst_case() {
+        let mut search = endgame(Objective::WorstCase);
+        assert_eq!(search.solve(&[0, 1]).0, 2);
+        assert_eq!(search.solve(&ANSWERS).0, 2);
+        assert_eq!(search.rank(&ANSWERS)[1], (0, 3.0));
+    }
+
+    #[test]
+    fn breadth_limit_gives_an_upper_bound() {
+        let optimum = endgame(Objective::Expected).solve(&ANSWERS).0;
+        for breadth in 1..=5 {
+            let mut search = endgame(Objective::Expected);
+            search.set_breadth(Some(breadth));
+            assert!(search.solve(&ANSWERS).0 >= optimum);
+        }
+    }
+}
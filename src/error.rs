@@ -0,0 +1,91 @@
+use std::fmt;
+use std::fs;
+use std::io;
+
+/// Errors returned by the library.
+#[derive(Debug)]
+pub enum Error {
+    /// A file couldn't be read or written.
+    Io { path: String, source: io::Error },
+    /// Reading from or writing to the terminal failed.
+    Terminal(io::Error),
+    /// A JSON file isn't valid JSON, or doesn't have the expected shape.
+    Json { path: String, source: serde_json::Error },
+    /// A line of a text file is malformed. `line` starts at 1.
+    Syntax { path: String, line: usize, message: String },
+    /// The solver config is invalid; `message` names the bad field.
+    Config { path: String, message: String },
+    /// A name that doesn't select anything, e.g. an unknown strategy.
+    UnknownName { kind: &'static str, name: String, expected: &'static str },
+    /// A word that is not in the word list.
+    UnknownWord(String),
+    /// A word that isn't five letters a-z.
+    InvalidWord(String),
+    /// A feedback pattern that isn't five of `w`, `m` and `c`.
+    InvalidPattern(String),
+    /// A date that isn't `YYYY-MM-DD`.
+    InvalidDate(String),
+    /// The word list has no usable words.
+    EmptyWordList(String),
+    /// Every possible answer was ruled out.
+    NoAnswers,
+    /// Settings or inputs that can't be used together.
+    Invalid(String),
+}
+
+/// Result type of the library.
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "Failed to access {}: {}", path, source),
+            Self::Terminal(source) => write!(f, "Failed to use the terminal: {}", source),
+            Self::Json { path, source } => write!(f, "Failed to parse {}: {}", path, source),
+            Self::Syntax { path, line, message } => write!(f, "{}:{}: {}", path, line, message),
+            Self::Config { path, message } => write!(f, "{}: {}", path, message),
+            Self::UnknownName { kind, name, expected } => {
+                write!(f, "Unknown {} '{}' (expected {})", kind, name, expected)
+            }
+            Self::UnknownWord(word) => write!(f, "'{}' is not in the wordlist", word),
+            Self::InvalidWord(word) => write!(f, "'{}' is not a 5-letter word", word),
+            Self::InvalidPattern(pattern) => {
+                write!(f, "Invalid pattern '{}' (expected 5 of w = wrong, m = misplaced, c = correct)", pattern)
+            }
+            Self::InvalidDate(date) => write!(f, "Invalid date '{}' (expected YYYY-MM-DD)", date),
+            Self::EmptyWordList(path) => write!(f, "{} is empty or invalid", path),
+            Self::NoAnswers => write!(f, "No answers left to choose from"),
+            Self::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } | Self::Terminal(source) => Some(source),
+            Self::Json { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Plain I/O errors come from the interactive modes; file access goes
+/// through `read_file` and `write_file`, which keep the path.
+impl From<io::Error> for Error {
+    fn from(source: io::Error) -> Self {
+        Self::Terminal(source)
+    }
+}
+
+pub(crate) fn read_file(path: &str) -> Result<String> {
+    fs::read_to_string(path).map_err(|source| Error::Io { path: path.to_string(), source })
+}
+
+pub(crate) fn write_file(path: &str, content: impl AsRef<[u8]>) -> Result<()> {
+    fs::write(path, content).map_err(|source| Error::Io { path: path.to_string(), source })
+}
+
+pub(crate) fn parse_json<T: serde::de::DeserializeOwned>(content: &str, path: &str) -> Result<T> {
+    serde_json::from_str(content).map_err(|source| Error::Json { path: path.to_string(), source })
+}
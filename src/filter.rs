@@ -1,72 +1,299 @@
-use std::collections::HashSet;
-use crate::game::GameData;
+use crate::error::{Error, Result};
+use crate::game::{CellState, GameData, LineData, Pattern};
+use crate::patterns::pattern_code;
+use crate::wordset::{Bitset, Word, WordSet};
+use std::fmt;
+
+/// The rules the feedback so far puts on the answer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// A letter marked correct must be at its position.
+    GreenMismatch,
+    /// A letter marked misplaced can't be at its position.
+    YellowPosition,
+    /// A letter marked wrong, and never correct or misplaced, can't be in the word.
+    AbsentLetter,
+    /// A letter marked correct or misplaced must be in the word.
+    LetterCount,
+}
+
+impl Rule {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::GreenMismatch => "green mismatch",
+            Self::YellowPosition => "yellow position",
+            Self::AbsentLetter => "absent letter",
+            Self::LetterCount => "letter count",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Check {
+    At(usize),
+    NotAt(usize),
+    Present,
+    Absent,
+}
+
+/// One requirement of the game, with the first line and cell it comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Constraint {
+    /// Index of the line.
+    pub line: usize,
+    pub cell: usize,
+    pub letter: char,
+    pub rule: Rule,
+    check: Check,
+}
+
+impl Constraint {
+    fn allows(&self, word: &[u8]) -> bool {
+        let letter = self.letter as u8;
+        match self.check {
+            Check::At(pos) => word[pos] == letter,
+            Check::NotAt(pos) => word[pos] != letter,
+            Check::Present => word.contains(&letter),
+            Check::Absent => !word.contains(&letter),
+        }
+    }
+
+    fn apply(&self, words: &WordSet, result: &mut Bitset) {
+        match self.check {
+            Check::At(pos) => result.intersect_with(words.with_letter_at(pos, self.letter)),
+            Check::NotAt(pos) => result.difference_with(words.with_letter_at(pos, self.letter)),
+            Check::Present => result.intersect_with(words.with_letter(self.letter)),
+            Check::Absent => result.difference_with(words.with_letter(self.letter)),
+        }
+    }
+}
+
+/// Why `Filter::explain` rejects a word: the first constraint it breaks.
+#[derive(Debug, Clone)]
+pub struct Elimination {
+    pub constraint: Constraint,
+    /// The line the constraint comes from.
+    pub line: LineData,
+    /// The rejected word.
+    pub word: String,
+}
+
+impl fmt::Display for Elimination {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Constraint { line, cell, letter, rule, check } = self.constraint;
+        write!(
+            f,
+            "line {} ({} {}), cell {}, {}: ",
+            line + 1, self.line.word, self.line.pattern(), cell + 1, rule.label()
+        )?;
+
+        match check {
+            Check::At(pos) => write!(
+                f,
+                "'{}' is correct at position {}, but '{}' has '{}' there",
+                letter, pos + 1, self.word, self.word.as_bytes()[pos] as char
+            ),
+            Check::NotAt(pos) => write!(f, "'{}' can't be at position {}, but '{}' has it there", letter, pos + 1, self.word),
+            Check::Present => write!(f, "'{}' must be in the word, but '{}' has none", letter, self.word),
+            Check::Absent => write!(f, "there is no '{}', but '{}' has one", letter, self.word),
+        }
+    }
+}
 
 pub struct Filter<'a> {
     game: &'a GameData,
-    words: &'a [String],
+    words: &'a WordSet,
 }
 
 impl<'a> Filter<'a> {
-    pub fn new(game: &'a GameData, words: &'a [String]) -> Self {
+    pub fn new(game: &'a GameData, words: &'a WordSet) -> Self {
         Self { game, words }
     }
 
-    pub fn filter_words(&self) -> Vec<String> {
-        self.words
-            .iter()
-            .filter(|word| {
-                let chars: Vec<char> = word.chars().collect();
-
-                self.not_strictly_forbidden(&chars)
-                    && self.matches_correct_positions(&chars)
-                    && self.respects_misplaced_constraints(&chars)
-                    && self.contains_required_letters(&chars)
-            })
-            .cloned()
-            .collect()
-    }
-
-    fn not_strictly_forbidden(&self, chars: &[char]) -> bool {
-        for ch in chars {
-            if self.game.contains_not.contains(ch) && !self.game.must_contain.contains(ch) {
-                return false;
-            }
+    /// Returns the subset of `candidates` consistent with the game so far.
+    pub fn filter_words(&self, candidates: &Bitset) -> Bitset {
+        let mut result = candidates.clone();
+
+        for constraint in self.constraints() {
+            constraint.apply(self.words, &mut result);
+        }
+
+        result
+    }
+
+    /// `filter_words`, narrowed down to the words that give exactly the
+    /// entered patterns. The rules don't count repeated letters, so they can
+    /// keep words that give other patterns, e.g. an earlier wrong guess.
+    pub fn consistent_words(&self, candidates: &Bitset) -> Bitset {
+        let filtered = self.filter_words(candidates);
+
+        let mut result = Bitset::empty(self.words.len());
+        for index in filtered.iter().filter(|&index| self.mismatch(self.words.word(index)).is_none()) {
+            result.insert(index);
         }
-        true
+        result
     }
 
-    fn matches_correct_positions(&self, chars: &[char]) -> bool {
-        for (i, maybe_correct) in self.game.correct_positions.iter().enumerate() {
-            if let Some(expected) = maybe_correct {
-                if chars[i] != *expected {
-                    return false;
+    /// The first line whose pattern `word` wouldn't give as the answer, with
+    /// the pattern it gives instead.
+    pub fn mismatch(&self, word: &Word) -> Option<(usize, Pattern)> {
+        self.game.lines.iter().enumerate().find_map(|(index, line)| {
+            let code = pattern_code(line.word.as_bytes(), word);
+            (code != line.pattern().code()).then(|| (index, Pattern::from_code(code)))
+        })
+    }
+
+    /// Returns the first constraint `word` breaks, or `None` if it is
+    /// consistent with the game. Uses the same constraints as `filter_words`.
+    pub fn explain(&self, word: &str) -> Result<Option<Elimination>> {
+        if word.len() != 5 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+            return Err(Error::InvalidWord(word.to_string()));
+        }
+
+        let bytes = word.as_bytes();
+        Ok(self
+            .constraints()
+            .into_iter()
+            .find(|constraint| !constraint.allows(bytes))
+            .map(|constraint| Elimination {
+                constraint,
+                line: self.game.lines[constraint.line].clone(),
+                word: word.to_string(),
+            }))
+    }
+
+    /// The game's constraints, each from the first cell that shows it, in
+    /// line and cell order.
+    pub fn constraints(&self) -> Vec<Constraint> {
+        let game = self.game;
+        let mut constraints: Vec<Constraint> = Vec::new();
+
+        for (line, line_data) in game.lines.iter().enumerate() {
+            for (cell, cell_data) in line_data.cells.iter().enumerate() {
+                let letter = cell_data.letter;
+                let required = game.must_contain.contains(&letter);
+                let shown = match cell_data.state {
+                    // A later line may have marked another letter correct here
+                    CellState::Correct if game.correct_positions[cell] == Some(letter) => {
+                        vec![(Rule::GreenMismatch, Check::At(cell)), (Rule::LetterCount, Check::Present)]
+                    }
+                    CellState::Correct => vec![(Rule::LetterCount, Check::Present)],
+                    CellState::Misplaced => {
+                        vec![(Rule::YellowPosition, Check::NotAt(cell)), (Rule::LetterCount, Check::Present)]
+                    }
+                    CellState::Wrong if !required => vec![(Rule::AbsentLetter, Check::Absent)],
+                    CellState::Wrong => Vec::new(),
+                };
+
+                for (rule, check) in shown {
+                    if !constraints.iter().any(|c| c.letter == letter && c.check == check) {
+                        constraints.push(Constraint { line, cell, letter, rule, check });
+                    }
                 }
             }
         }
-        true
+
+        constraints
     }
+}
 
-    fn respects_misplaced_constraints(&self, chars: &[char]) -> bool {
-        for (pos, letters) in &self.game.misplaced_letters {
-            for &letter in letters {
-                if chars[*pos] == letter {
-                    return false;
-                }
-                if !chars.contains(&letter) {
-                    return false;
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::play::evaluate;
+    use crate::wordlist;
+
+    /// Answers and guesses with repeated letters in the guess, the answer or both.
+    const HISTORIES: [(&str, &[&str]); 6] = [
+        ("salsa", &["salet", "salon", "salic", "salps", "salad"]),
+        ("lathe", &["salet", "table"]),
+        ("abhor", &["salet", "moray", "aroid"]),
+        ("alloy", &["salet", "allay"]),
+        ("geese", &["eerie", "speed"]),
+        ("tepee", &["crepe", "eject"]),
+    ];
+
+    fn game(answer: &str, guesses: &[&str]) -> GameData {
+        let mut game = GameData::new();
+        for guess in guesses {
+            game.add_line(guess, &evaluate(guess, answer).pattern()).unwrap();
+        }
+        game
+    }
+
+    /// The rules of the filter, checked word by word on the game's summary.
+    fn per_word(game: &GameData, word: &str) -> bool {
+        let chars: Vec<char> = word.chars().collect();
+        let forbidden = chars
+            .iter()
+            .any(|c| game.contains_not.contains(c) && !game.must_contain.contains(c));
+        let correct = game
+            .correct_positions
+            .iter()
+            .enumerate()
+            .all(|(i, expected)| expected.is_none_or(|e| chars[i] == e));
+        let misplaced = game
+            .misplaced_letters
+            .iter()
+            .all(|(&pos, letters)| letters.iter().all(|l| chars[pos] != *l && chars.contains(l)));
+        let required = game.must_contain.iter().all(|c| chars.contains(c));
+
+        !forbidden && correct && misplaced && required
+    }
+
+    #[test]
+    fn filter_words_agrees_with_per_word_rules() {
+        let words = wordlist::load_words().unwrap();
+        let set = WordSet::new(&words).unwrap();
+
+        for (answer, guesses) in HISTORIES {
+            let game = game(answer, guesses);
+            let filter = Filter::new(&game, &set);
+            let kept = filter.filter_words(&set.all());
+
+            for (index, word) in words.iter().enumerate() {
+                assert_eq!(kept.contains(index), per_word(&game, word), "{} for {:?}", word, guesses);
+                assert_eq!(kept.contains(index), filter.explain(word).unwrap().is_none(), "{} for {:?}", word, guesses);
             }
         }
-        true
     }
 
-    fn contains_required_letters(&self, chars: &[char]) -> bool {
-        let present: HashSet<char> = chars.iter().copied().collect();
-        for &req in &self.game.must_contain {
-            if !present.contains(&req) {
-                return false;
+    #[test]
+    fn consistent_words_agrees_with_evaluate() {
+        let words = wordlist::load_words().unwrap();
+        let set = WordSet::new(&words).unwrap();
+
+        for (answer, guesses) in HISTORIES {
+            let game = game(answer, guesses);
+            let kept = Filter::new(&game, &set).consistent_words(&set.all());
+
+            for (index, word) in words.iter().enumerate() {
+                let expected = game.lines.iter().all(|line| evaluate(&line.word, word).pattern() == line.pattern());
+                assert_eq!(kept.contains(index), expected, "{} for {:?}", word, guesses);
             }
+            assert!(kept.contains(set.index_of(answer).unwrap()));
         }
-        true
+    }
+
+    #[test]
+    fn rules_miss_repeated_letters() {
+        // 'allay' gives ccccc for itself, but the rules don't count its second 'a'
+        let words = wordlist::load_words().unwrap();
+        let set = WordSet::new(&words).unwrap();
+        let game = game("alloy", &["salet", "allay"]);
+        let filter = Filter::new(&game, &set);
+        let allay = set.index_of("allay").unwrap();
+
+        assert!(filter.filter_words(&set.all()).contains(allay));
+        assert!(!filter.consistent_words(&set.all()).contains(allay));
+        assert_eq!(filter.mismatch(set.word(allay)), Some((1, Pattern::SOLVED)));
+    }
+
+    #[test]
+    fn explain_rejects_malformed_words() {
+        let set = WordSet::new(&["salet".to_string()]).unwrap();
+        let game = GameData::new();
+        assert!(matches!(Filter::new(&game, &set).explain("ab"), Err(Error::InvalidWord(_))));
+        assert!(matches!(Filter::new(&game, &set).explain("SALET"), Err(Error::InvalidWord(_))));
     }
 }
@@ -1,10 +1,153 @@
+use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 use serde::{Serialize, Deserialize};
+use crate::error::{Error, Result};
+
+/// Feedback for one letter of a guess. Serialised as its pattern letter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(into = "char", try_from = "char")]
+pub enum CellState {
+    /// `w`: the letter is not in the word (or not as often as guessed).
+    Wrong = 0,
+    /// `m`: the letter is in the word, but somewhere else.
+    Misplaced = 1,
+    /// `c`: the letter is in the right place.
+    Correct = 2,
+}
+
+impl CellState {
+    /// The letter used for the state in patterns.
+    pub fn letter(self) -> char {
+        match self {
+            Self::Wrong => 'w',
+            Self::Misplaced => 'm',
+            Self::Correct => 'c',
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Wrong => "wrong",
+            Self::Misplaced => "misplaced",
+            Self::Correct => "correct",
+        }
+    }
+}
+
+impl From<CellState> for char {
+    fn from(state: CellState) -> Self {
+        state.letter()
+    }
+}
+
+impl TryFrom<char> for CellState {
+    type Error = Error;
+
+    fn try_from(letter: char) -> Result<Self> {
+        match letter {
+            'w' => Ok(Self::Wrong),
+            'm' => Ok(Self::Misplaced),
+            'c' => Ok(Self::Correct),
+            _ => Err(Error::InvalidPattern(letter.to_string())),
+        }
+    }
+}
+
+/// The feedback for a whole guess, written as five state letters, e.g. `wcmmm`.
+/// Ordered like those strings, so maps keyed by patterns list `c` before `w`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Pattern([CellState; 5]);
+
+impl Pattern {
+    /// `ccccc`, the pattern of guessing the answer.
+    pub const SOLVED: Self = Self([CellState::Correct; 5]);
+
+    pub fn new(states: [CellState; 5]) -> Self {
+        Self(states)
+    }
+
+    pub fn states(&self) -> &[CellState; 5] {
+        &self.0
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Self::SOLVED
+    }
+
+    /// The pattern as the base-3 code of `patterns::pattern_code`.
+    pub fn code(&self) -> u8 {
+        self.0.iter().rev().fold(0, |code, &state| code * 3 + state as u8)
+    }
+
+    /// The pattern of a code from `patterns::pattern_code`.
+    pub fn from_code(mut code: u8) -> Self {
+        let mut states = [CellState::Wrong; 5];
+        for state in &mut states {
+            *state = match code % 3 {
+                0 => CellState::Wrong,
+                1 => CellState::Misplaced,
+                _ => CellState::Correct,
+            };
+            code /= 3;
+        }
+        Self(states)
+    }
+}
+
+impl Ord for Pattern {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // The state letters sort in the opposite order of the states
+        other.0.cmp(&self.0)
+    }
+}
+
+impl PartialOrd for Pattern {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidPattern(s.to_string());
+        let states: Vec<CellState> = s
+            .chars()
+            .map(|letter| CellState::try_from(letter).map_err(|_| invalid()))
+            .collect::<Result<_>>()?;
+
+        states.try_into().map(Self).map_err(|_| invalid())
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|state| write!(f, "{}", state.letter()))
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.to_string()
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CellData {
     pub letter: char,
-    pub state: char,
+    pub state: CellState,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +165,19 @@ pub struct GameData {
     pub must_contain: HashSet<char>,
 }
 
+impl LineData {
+    /// Returns the line's states as a pattern, e.g. `wcmmm`.
+    pub fn pattern(&self) -> Pattern {
+        Pattern(self.cells.clone().map(|cell| cell.state))
+    }
+}
+
+impl Default for GameData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl GameData {
     pub fn new() -> Self {
         Self {
@@ -33,35 +189,39 @@ impl GameData {
         }
     }
 
-    pub fn add_line(&mut self, word: &str, pattern: &str) {
+    /// Records a guess and its feedback pattern.
+    pub fn add_line(&mut self, word: &str, pattern: &Pattern) -> Result<()> {
+        if word.len() != 5 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+            return Err(Error::InvalidWord(word.to_string()));
+        }
+
         let mut cells = Vec::new();
 
-        for (i, (ch, state)) in word.chars().zip(pattern.chars()).enumerate() {
+        for (i, (ch, &state)) in word.chars().zip(pattern.states()).enumerate() {
             cells.push(CellData { letter: ch, state });
 
             match state {
-                'c' => {
+                CellState::Correct => {
                     self.correct_positions[i] = Some(ch);
                     self.must_contain.insert(ch);
                 }
-                'm' => {
+                CellState::Misplaced => {
                     self.misplaced_letters.entry(i).or_default().insert(ch);
                     self.must_contain.insert(ch);
                 }
-                'w' => {
-                    if !self.must_contain.contains(&ch) {
-                        self.contains_not.insert(ch);
-                    }
+                CellState::Wrong if !self.must_contain.contains(&ch) => {
+                    self.contains_not.insert(ch);
                 }
-                _ => {}
+                CellState::Wrong => {}
             }
         }
 
-        let cells: [CellData; 5] = cells.try_into().expect("must be 5 letters");
+        let cells: [CellData; 5] = cells.try_into().expect("checked to be 5 letters");
         self.lines.push(LineData {
             word: word.to_string(),
             cells,
         });
+        Ok(())
     }
 
     pub fn print_summary(&self) {
@@ -74,6 +234,40 @@ impl GameData {
         println!("==========================\n");
     }
 
+    /// Removes the line at `index`.
+    pub fn remove_line(&mut self, index: usize) -> Result<()> {
+        self.check_index(index)?;
+        let mut lines = self.lines.clone();
+        lines.remove(index);
+        self.replay(lines)
+    }
+
+    /// Replaces the pattern of the line at `index`.
+    pub fn set_pattern(&mut self, index: usize, pattern: &Pattern) -> Result<()> {
+        self.check_index(index)?;
+        let mut lines = self.lines.clone();
+        for (cell, &state) in lines[index].cells.iter_mut().zip(pattern.states()) {
+            cell.state = state;
+        }
+        self.replay(lines)
+    }
+
+    fn check_index(&self, index: usize) -> Result<()> {
+        if index >= self.lines.len() {
+            return Err(Error::Invalid(format!("No line {} in a game of {} lines", index + 1, self.lines.len())));
+        }
+        Ok(())
+    }
+
+    /// Rebuilds the state from scratch, since a constraint can't be taken back.
+    fn replay(&mut self, lines: Vec<LineData>) -> Result<()> {
+        self.reset();
+        for line in &lines {
+            self.add_line(&line.word, &line.pattern())?;
+        }
+        Ok(())
+    }
+
     pub fn reset(&mut self) {
         self.lines.clear();
         self.contains_not.clear();
@@ -82,3 +276,127 @@ impl GameData {
         self.must_contain.clear();
     }
 }
+
+/// Parses a guess history of `word:pattern` entries separated by commas or
+/// whitespace, e.g. `salet:wcmmm,table:mcwmc` (the lines of `tree.txt` work too).
+pub fn parse_history(text: &str) -> Result<Vec<(String, Pattern)>> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let Some((word, pattern)) = entry.split_once(':') else {
+                return Err(Error::Invalid(format!("Expected '<word>:<pattern>', got '{}'", entry)));
+            };
+            Ok((word.to_lowercase(), pattern.to_lowercase().parse()?))
+        })
+        .collect()
+}
+
+/// Reads a history file in the format of `parse_history`, with any number of
+/// entries per line. Empty lines and lines starting with `#` are skipped.
+pub fn load_history(path: &str) -> Result<Vec<(String, Pattern)>> {
+    let content = crate::error::read_file(path)?;
+
+    let mut history = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let entries = parse_history(line)
+            .map_err(|e| Error::Syntax { path: path.to_string(), line: number + 1, message: e.to_string() })?;
+        history.extend(entries);
+    }
+
+    Ok(history)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::pattern_code;
+    use crate::ranking::PATTERN_COUNT;
+    use std::collections::BTreeMap;
+
+    fn pattern(s: &str) -> Pattern {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn patterns_sort_like_their_letters() {
+        assert!(pattern("ccccc") < pattern("mcccc"));
+        assert!(pattern("mcccc") < pattern("wcccc"));
+        assert!(pattern("cwwww") < pattern("mwwww"));
+        assert!(pattern("wcwww") < pattern("wwccc"));
+
+        let mut patterns: Vec<Pattern> = (0..PATTERN_COUNT as u8).map(Pattern::from_code).collect();
+        patterns.sort();
+        let strings: Vec<String> = patterns.iter().map(Pattern::to_string).collect();
+        let mut sorted = strings.clone();
+        sorted.sort();
+        assert_eq!(strings, sorted);
+        assert_eq!(strings.first().map(String::as_str), Some("ccccc"));
+        assert_eq!(strings.last().map(String::as_str), Some("wwwww"));
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for code in 0..PATTERN_COUNT as u8 {
+            assert_eq!(Pattern::from_code(code).code(), code);
+        }
+        assert_eq!(Pattern::SOLVED.code(), 242);
+        assert_eq!(pattern("wwwww").code(), 0);
+        // The first letter is the lowest digit
+        assert_eq!(pattern("mwwww").code(), 1);
+        assert_eq!(pattern("wcwww").code(), 6);
+        assert_eq!(pattern_code(b"salet", b"slate"), pattern("cmmmm").code());
+        assert_eq!(Pattern::from_code(pattern_code(b"allay", b"alloy")).to_string(), "cccwc");
+    }
+
+    #[test]
+    fn pattern_maps_serialize_as_strings() {
+        let map: BTreeMap<Pattern, usize> = [(pattern("wwwww"), 3), (pattern("ccccc"), 1), (pattern("mwcww"), 2)].into();
+        let json = serde_json::to_string(&map).unwrap();
+        assert_eq!(json, r#"{"ccccc":1,"mwcww":2,"wwwww":3}"#);
+
+        let parsed: BTreeMap<Pattern, usize> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, map);
+        assert!(serde_json::from_str::<BTreeMap<Pattern, usize>>(r#"{"ccxcc":1}"#).is_err());
+        assert!(serde_json::from_str::<BTreeMap<Pattern, usize>>(r#"{"cccc":1}"#).is_err());
+    }
+
+    #[test]
+    fn parses_history() {
+        let expected = vec![("salet".to_string(), pattern("wmcww")), ("allay".to_string(), pattern("cccwc"))];
+        assert_eq!(parse_history("salet:wmcww,allay:cccwc").unwrap(), expected);
+        assert_eq!(parse_history(" SALET:WMCWW \n\tallay:cccwc,, ").unwrap(), expected);
+        assert!(parse_history("").unwrap().is_empty());
+
+        assert!(matches!(parse_history("salet wmcww"), Err(Error::Invalid(_))));
+        assert!(matches!(parse_history("salet:wmcw"), Err(Error::InvalidPattern(_))));
+    }
+
+    #[test]
+    fn loads_history_files() {
+        let path = std::env::temp_dir().join(format!("wordle-bot-history-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        std::fs::write(path, "# first game\nsalet:wmcww\n\n  # guesses 2 and 3\nALLAY:cccwc, alloy:ccccc\n").unwrap();
+        let history = load_history(path).unwrap();
+        let words: Vec<&str> = history.iter().map(|(word, _)| word.as_str()).collect();
+        assert_eq!(words, ["salet", "allay", "alloy"]);
+        assert!(history[2].1.is_solved());
+
+        std::fs::write(path, "salet:wmcww\n# comment\nallay:cccwc alloy\n").unwrap();
+        let result = load_history(path);
+        std::fs::remove_file(path).unwrap();
+        match result {
+            Err(Error::Syntax { path: error_path, line, message }) => {
+                assert_eq!(error_path, path);
+                assert_eq!(line, 3);
+                assert!(message.contains("'alloy'"), "{}", message);
+            }
+            other => panic!("expected a syntax error, got {:?}", other),
+        }
+    }
+}
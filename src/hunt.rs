@@ -0,0 +1,281 @@
+use crate::endgame::EndgameConfig;
+use crate::error::{Error, Result};
+use crate::filter::Filter;
+use crate::game::{GameData, Pattern};
+use crate::play::evaluate;
+use crate::solver::rank_guesses;
+use crate::strategy::Strategy;
+use crate::wordlist;
+use crate::wordset::{Bitset, WordSet};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+/// Games still unsolved after this many guesses are given up.
+pub const GUESS_LIMIT: usize = 20;
+
+/// Which cases the hunter reports, and how the solver plays.
+#[derive(Debug, Clone)]
+pub struct HuntConfig {
+    /// Solved games needing more guesses than this are reported.
+    pub max_guesses: usize,
+    /// Suggestions listed after every guess of a case.
+    pub top_n: usize,
+    pub hard_mode: bool,
+    pub endgame: EndgameConfig,
+    /// Start with every allowed word as a candidate, as `solve` does, instead
+    /// of the answers, as `tree`, `openers`, `tune` and `compare` do.
+    pub all_words: bool,
+}
+
+impl HuntConfig {
+    /// The words the candidates start from, for reports.
+    pub fn candidates_label(&self) -> &'static str {
+        if self.all_words {
+            "every allowed word, as in solve"
+        } else {
+            "the answers, as in tree, openers, tune and compare"
+        }
+    }
+}
+
+impl Default for HuntConfig {
+    fn default() -> Self {
+        Self {
+            max_guesses: 6,
+            top_n: 10,
+            hard_mode: false,
+            endgame: EndgameConfig::default(),
+            all_words: false,
+        }
+    }
+}
+
+/// How a reported game ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Solved, but with more guesses than allowed.
+    TooManyGuesses,
+    /// The filter dropped the answer from the candidates.
+    AnswerFilteredOut,
+    /// Not solved within `GUESS_LIMIT` guesses, or no suggestion was left.
+    Unsolved,
+}
+
+/// A guess of a game, with what the solver showed after it was entered.
+#[derive(Debug, Clone)]
+pub struct Turn {
+    pub guess: String,
+    pub pattern: Pattern,
+    /// The top suggestions for the next guess.
+    pub suggestions: Vec<(String, f64)>,
+    pub words_left: usize,
+}
+
+/// A game of the solver worth a look.
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub answer: String,
+    pub outcome: Outcome,
+    pub turns: Vec<Turn>,
+}
+
+/// The result of playing every answer.
+#[derive(Debug, Clone, Default)]
+pub struct Hunt {
+    /// Number of guesses of every solved answer.
+    pub guesses: BTreeMap<String, usize>,
+    /// Reported games, sorted by outcome and answer.
+    pub cases: Vec<Case>,
+}
+
+/// Plays the live solver (filter, strategy and endgame search, as in `solve`)
+/// against every answer and collects the games that go wrong. The candidates
+/// start as the answers unless `HuntConfig::all_words` is set.
+pub struct Hunter<'a> {
+    words: WordSet,
+    answers_start: usize,
+    /// Answers that aren't played and aren't candidates, e.g. already used ones.
+    excluded: Bitset,
+    strategy: &'a dyn Strategy,
+    config: HuntConfig,
+}
+
+impl<'a> Hunter<'a> {
+    pub fn new(words: &[String], strategy: &'a dyn Strategy, config: HuntConfig) -> Result<Self> {
+        Ok(Self {
+            answers_start: words.len() - wordlist::answers(words).len(),
+            excluded: Bitset::empty(words.len()),
+            words: WordSet::new(words)?,
+            strategy,
+            config,
+        })
+    }
+
+    /// Leaves these words out of the answers played and the candidates, like
+    /// `Solver::exclude`.
+    pub fn exclude<'w>(&mut self, words: impl Iterator<Item = &'w str>) {
+        for index in words.filter_map(|word| self.words.index_of(word)) {
+            self.excluded.insert(index);
+        }
+    }
+
+    /// Plays every answer starting with `opener`. Games with the same
+    /// feedback so far are played together, since the solver is deterministic.
+    pub fn hunt(&self, opener: &str) -> Result<Hunt> {
+        if self.words.index_of(opener).is_none() {
+            return Err(Error::UnknownWord(opener.to_string()));
+        }
+
+        let answers: Vec<usize> = (self.answers_start..self.words.len())
+            .filter(|&answer| !self.excluded.contains(answer))
+            .collect();
+        let candidates = if self.config.all_words {
+            let mut candidates = self.words.all();
+            candidates.difference_with(&self.excluded);
+            candidates
+        } else {
+            let mut candidates = Bitset::empty(self.words.len());
+            for &answer in &answers {
+                candidates.insert(answer);
+            }
+            candidates
+        };
+        let mut hunt = self.play(&GameData::new(), &candidates, &answers, &[], opener)?;
+        hunt.cases.sort_by_key(|case| (case.outcome as u8, case.answer.clone()));
+
+        Ok(hunt)
+    }
+
+    fn play(&self, game: &GameData, candidates: &Bitset, answers: &[usize], turns: &[Turn], guess: &str) -> Result<Hunt> {
+        let mut groups: BTreeMap<Pattern, Vec<usize>> = BTreeMap::new();
+        for &answer in answers {
+            groups.entry(evaluate(guess, self.words.as_str(answer)).pattern()).or_default().push(answer);
+        }
+
+        let hunts: Vec<Hunt> = groups
+            .into_par_iter()
+            .map(|(pattern, group)| self.play_group(game, candidates, &group, turns, guess, pattern))
+            .collect::<Result<_>>()?;
+
+        let mut hunt = Hunt::default();
+        for part in hunts {
+            hunt.guesses.extend(part.guesses);
+            hunt.cases.extend(part.cases);
+        }
+        Ok(hunt)
+    }
+
+    /// Continues the games of the answers giving `pattern` for `guess`.
+    fn play_group(
+        &self,
+        game: &GameData,
+        candidates: &Bitset,
+        answers: &[usize],
+        turns: &[Turn],
+        guess: &str,
+        pattern: Pattern,
+    ) -> Result<Hunt> {
+        let mut hunt = Hunt::default();
+        let mut game = game.clone();
+        game.add_line(guess, &pattern)?;
+
+        let candidates = Filter::new(&game, &self.words).consistent_words(candidates);
+        let ranked = if pattern.is_solved() {
+            Vec::new()
+        } else {
+            rank_guesses(self.strategy, &self.config.endgame, self.config.hard_mode, &self.words, &candidates, turns.len() + 1)
+                .ranked
+        };
+
+        let mut turns = turns.to_vec();
+        turns.push(Turn {
+            guess: guess.to_string(),
+            pattern,
+            suggestions: ranked.iter().take(self.config.top_n).cloned().collect(),
+            words_left: candidates.count(),
+        });
+
+        let case = |answer: usize, outcome| Case {
+            answer: self.words.as_str(answer).to_string(),
+            outcome,
+            turns: turns.clone(),
+        };
+
+        if pattern.is_solved() {
+            for &answer in answers {
+                hunt.guesses.insert(self.words.as_str(answer).to_string(), turns.len());
+                if turns.len() > self.config.max_guesses {
+                    hunt.cases.push(case(answer, Outcome::TooManyGuesses));
+                }
+            }
+            return Ok(hunt);
+        }
+
+        let (left, filtered): (Vec<usize>, Vec<usize>) = answers.iter().partition(|&&a| candidates.contains(a));
+        hunt.cases.extend(filtered.into_iter().map(|answer| case(answer, Outcome::AnswerFilteredOut)));
+
+        let next = ranked.first().map(|(word, _)| word.as_str());
+        match next {
+            Some(next) if turns.len() < GUESS_LIMIT && !left.is_empty() => {
+                let rest = self.play(&game, &candidates, &left, &turns, next)?;
+                hunt.guesses.extend(rest.guesses);
+                hunt.cases.extend(rest.cases);
+            }
+            _ => hunt.cases.extend(left.into_iter().map(|answer| case(answer, Outcome::Unsolved))),
+        }
+
+        Ok(hunt)
+    }
+}
+
+/// Markdown report of the cases in the table format of docs/Solver.md.
+pub fn report(hunt: &Hunt, opener: &str, config: &HuntConfig) -> String {
+    let mut out = String::new();
+    let solved = hunt.guesses.len();
+    let total: usize = hunt.guesses.values().sum();
+
+    let _ = writeln!(out, "# Edge Cases\n");
+    let _ = writeln!(out, "Found by playing the solver with opener `{}` against every answer.\n", opener);
+    let _ = writeln!(out, "* Candidates: {}", config.candidates_label());
+    let _ = writeln!(out, "* Answers solved: {}", solved);
+    if solved > 0 {
+        let _ = writeln!(out, "* Average guesses: {:.4}", total as f64 / solved as f64);
+    }
+    for outcome in [Outcome::TooManyGuesses, Outcome::AnswerFilteredOut, Outcome::Unsolved] {
+        let count = hunt.cases.iter().filter(|case| case.outcome == outcome).count();
+        let label = match outcome {
+            Outcome::TooManyGuesses => format!("More than {} guesses", config.max_guesses),
+            Outcome::AnswerFilteredOut => "Answer filtered out".to_string(),
+            Outcome::Unsolved => format!("Not solved within {} guesses", GUESS_LIMIT),
+        };
+        let _ = writeln!(out, "* {}: {}", label, count);
+    }
+
+    for (number, case) in hunt.cases.iter().enumerate() {
+        let _ = writeln!(out, "\n## Number {}:\n", number + 1);
+        let summary = match case.outcome {
+            Outcome::TooManyGuesses => format!("Solved in {} guesses", case.turns.len()),
+            Outcome::AnswerFilteredOut => format!("The answer was filtered out after word {}", case.turns.len()),
+            Outcome::Unsolved => format!("Not solved after {} guesses", case.turns.len()),
+        };
+        let _ = writeln!(out, "> {}\n", summary);
+        let _ = writeln!(out, "| No. | Word  | Pattern |");
+        let _ = writeln!(out, "| --- | ----- | ------- |");
+        for (i, turn) in case.turns.iter().enumerate() {
+            let _ = writeln!(out, "| {:<3} | {} | {}   |", format!("{}.", i + 1), turn.guess, turn.pattern);
+        }
+        let _ = writeln!(out, "\nGoal Word was: {}", case.answer);
+
+        for (i, turn) in case.turns.iter().enumerate().filter(|(_, turn)| !turn.pattern.is_solved()) {
+            let _ = writeln!(out, "\nAfter entering word {} ({}):\n", i + 1, turn.guess);
+            let _ = writeln!(out, "- Top suggested words:");
+            for (word, score) in &turn.suggestions {
+                let _ = writeln!(out, "- {:<10} {:.5}", word, score);
+            }
+            let _ = writeln!(out, "- Total Words Left: {}", turn.words_left);
+        }
+    }
+
+    out
+}
@@ -0,0 +1,43 @@
+//! Wordle game and solver.
+//!
+//! The word list (`wordlist`), letter statistics (`analysis`) and scoring
+//! (`ranking`, `strategy`) feed the solver; `game` and `filter` track what the
+//! feedback so far allows, and `tree`, `openers`, `tune` and `compare` simulate
+//! the solver over every answer. All fallible functions return `Error`.
+
+pub mod analysis;
+pub mod compare;
+pub mod config;
+pub mod contradiction;
+pub mod endgame;
+pub mod error;
+pub mod filter;
+pub mod game;
+pub mod hunt;
+pub mod lookahead;
+pub mod openers;
+pub mod optimal;
+pub mod patterns;
+pub mod play;
+pub mod prior;
+pub mod ranking;
+pub mod selfcheck;
+pub mod solver;
+pub mod strategy;
+pub mod tree;
+pub mod tune;
+pub mod used;
+pub mod wordlist;
+pub mod wordset;
+
+pub use analysis::LetterStats;
+pub use config::SolverConfig;
+pub use error::{Error, Result};
+pub use filter::Filter;
+pub use game::GameData;
+pub use patterns::PatternMatrix;
+pub use play::Play;
+pub use solver::Solver;
+pub use strategy::Strategy;
+pub use tree::{TreeBuilder, TreeNode};
+pub use wordset::WordSet;
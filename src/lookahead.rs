@@ -0,0 +1,150 @@
+use crate::patterns::SOLVED_CODE;
+use crate::prior::Prior;
+use crate::ranking::{RankMethod, PATTERN_COUNT};
+use crate::strategy::{Context, Ranking};
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Settings of the two-step lookahead strategy.
+#[derive(Debug, Clone, Copy)]
+pub struct LookaheadConfig {
+    /// Ranking whose top guesses are looked ahead from. Must not be `Lookahead`.
+    pub base: RankMethod,
+    /// How many of the base ranking's guesses are evaluated.
+    pub top_k: usize,
+    /// Stop evaluating further guesses once this many patterns were scored.
+    pub max_nodes: u64,
+    /// Stop evaluating further guesses after this much time.
+    pub time_limit: Option<Duration>,
+}
+
+impl Default for LookaheadConfig {
+    fn default() -> Self {
+        Self {
+            base: RankMethod::Expected,
+            top_k: 10,
+            max_nodes: 500_000_000,
+            time_limit: None,
+        }
+    }
+}
+
+/// Re-ranks the top guesses of `base` by the expected total number of guesses
+/// when the best follow-up is played in every feedback bucket (smaller is
+/// better). Patterns come from the context's matrix when it has one, and with
+/// a prior every candidate counts with its probability of being the answer.
+///
+/// Guesses not evaluated within the budget, and guesses that don't split the
+/// candidates at all, are returned unranked in their base order with their
+/// base score. If no guess could be evaluated, the base ranking is returned.
+pub fn lookahead_rank(ctx: &Context, base: &[(String, f64)], config: &LookaheadConfig, prior: Option<&Prior>) -> Ranking {
+    let start = Instant::now();
+    let mut nodes = 0u64;
+
+    let probabilities = match prior {
+        Some(prior) => prior.probabilities(ctx.candidates),
+        None => vec![1.0 / ctx.candidates.len().max(1) as f64; ctx.candidates.len()],
+    };
+
+    let indices: HashMap<&str, usize> = ctx.guesses.iter().enumerate().map(|(i, &g)| (g, i)).collect();
+    let mut ranked = Vec::new();
+    let mut unranked = Vec::new();
+
+    for (word, score) in base {
+        let within_budget = !ctx.candidates.is_empty()
+            && ranked.len() < config.top_k
+            && nodes < config.max_nodes
+            && config.time_limit.is_none_or(|limit| start.elapsed() < limit);
+
+        if let Some(&guess) = indices.get(word.as_str()).filter(|_| within_budget) {
+            let (expected, cost) = expected_total(ctx, guess, &probabilities);
+            nodes += cost;
+            if let Some(expected) = expected {
+                ranked.push((word.clone(), expected));
+                continue;
+            }
+        }
+        unranked.push((word.clone(), *score));
+    }
+
+    if ranked.is_empty() {
+        return Ranking { ranked: unranked, unranked: Vec::new() };
+    }
+
+    // Stable, so ties keep the base order
+    ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+    Ranking { ranked, unranked }
+}
+
+/// Expected number of guesses to solve the candidates starting with `guess`,
+/// and the number of patterns scored to find out. `None` if the guess leaves
+/// all candidates in a single bucket, which can never be the best choice.
+fn expected_total(ctx: &Context, guess: usize, probabilities: &[f64]) -> (Option<f64>, u64) {
+    let candidates = ctx.candidates.len();
+    let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); PATTERN_COUNT];
+    for c in 0..candidates {
+        let code = ctx.code(guess, c);
+        if code != SOLVED_CODE {
+            buckets[code as usize].push(c);
+        }
+    }
+
+    let mut nodes = candidates as u64;
+    if buckets.iter().any(|b| b.len() == candidates) {
+        return (None, nodes);
+    }
+
+    let mut total = 0.0;
+    for bucket in buckets.iter().filter(|b| !b.is_empty()) {
+        let mass: f64 = bucket.iter().map(|&c| probabilities[c]).sum();
+        let cost = if bucket.len() == 1 {
+            1.0
+        } else {
+            nodes += (ctx.guesses.len() * bucket.len()) as u64;
+            best_follow_up(ctx, bucket, probabilities, mass)
+        };
+        total += mass * cost;
+    }
+
+    (Some(1.0 + total), nodes)
+}
+
+/// Expected guesses to finish a bucket with the best follow-up: the follow-up
+/// itself, plus `(m + 1) / 2` for every group of `m` words it leaves (trying
+/// them one by one), weighted by the group's probability. A solved follow-up
+/// adds nothing. Ties prefer follow-ups that are candidates, then word list order.
+fn best_follow_up(ctx: &Context, bucket: &[usize], probabilities: &[f64], mass: f64) -> f64 {
+    (0..ctx.guesses.len())
+        .into_par_iter()
+        .map_init(
+            || ([0u32; PATTERN_COUNT], [0.0; PATTERN_COUNT], Vec::new()),
+            |(sizes, masses, codes), h| {
+                codes.clear();
+                codes.extend(bucket.iter().map(|&c| ctx.code(h, c) as usize));
+
+                // Σ mass·(size + 1) / 2 over the groups, kept up to date as words are added
+                let mut weighted = 0.0;
+                for (&c, &code) in bucket.iter().zip(codes.iter()) {
+                    let p = probabilities[c];
+                    weighted += masses[code] + p * (sizes[code] as f64 + 2.0);
+                    sizes[code] += 1;
+                    masses[code] += p;
+                }
+
+                let solved = SOLVED_CODE as usize;
+                let is_candidate = sizes[solved] > 0;
+                let unsolved = (weighted - masses[solved] * (sizes[solved] as f64 + 1.0)) / 2.0;
+                let cost = if mass > 0.0 { 1.0 + unsolved / mass } else { 1.0 };
+
+                // Clear only the groups used, for the next guess
+                for &code in codes.iter() {
+                    sizes[code] = 0;
+                    masses[code] = 0.0;
+                }
+                (cost, !is_candidate, h)
+            },
+        )
+        .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)))
+        .map_or(1.0, |(cost, _, _)| cost)
+}
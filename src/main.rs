@@ -1,15 +1,29 @@
-mod analysis;
-mod ranking;
-mod solver;
-mod filter;
-mod game;
-mod play;
-
-use anyhow::Result;
+mod args;
+
+use anyhow::{anyhow, Result};
 use std::fs;
-use analysis::LetterStats;
-use solver::Solver;
-use play::Play;
+use args::Args;
+use wordle_bot::analysis::{LetterStats, STATS_PATH};
+use wordle_bot::config::{self, SolverConfig};
+use wordle_bot::endgame::Objective;
+use wordle_bot::game::{self, Pattern};
+use wordle_bot::hunt::{self, HuntConfig, Hunter};
+use wordle_bot::patterns::PatternMatrix;
+use wordle_bot::play::{self, Play};
+use wordle_bot::prior::{Prior, PriorConfig};
+use wordle_bot::ranking::{self, RankMethod};
+use wordle_bot::selfcheck::{Mismatch, SelfCheck};
+use wordle_bot::solver::Solver;
+use wordle_bot::strategy::{self, Strategy};
+use wordle_bot::tree::TreeBuilder;
+use wordle_bot::tune::{self, TuneConfig, Tuner};
+use wordle_bot::used::{self, UsedAnswers, UsedMode};
+use wordle_bot::{compare, openers, optimal, wordlist};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use std::path::Path;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 
 // TODO: Add simulate mode which plays game to caculate average number of guesses
 
@@ -17,15 +31,26 @@ fn main() -> Result<()> {
     let args: Vec<String> = std::env::args().collect();
 
     if args.len() < 2 {
-        eprintln!("Usage: wordle-bot <analyze|rank|solve>");
+        eprintln!("Usage: wordle-bot <analyze|rank|solve|play|tree|openers|optimal|used|tune|compare|config|selfcheck|hunt>");
         std::process::exit(1);
     }
 
+    configure_threads(&Args::parse(&args[2..]))?;
+
     match args[1].as_str() {
         "analyze" => analyze()?,
         "rank" => rank()?,
-        "solve" => solve()?,
-        "play" => play()?,
+        "solve" => solve(&Args::parse(&args[2..]))?,
+        "play" => play(&Args::parse(&args[2..]))?,
+        "tree" => tree(&Args::parse(&args[2..]))?,
+        "openers" => openers(&Args::parse(&args[2..]))?,
+        "optimal" => optimal(&Args::parse(&args[2..]))?,
+        "used" => used(&Args::parse(&args[2..]))?,
+        "tune" => tune(&Args::parse(&args[2..]))?,
+        "compare" => compare(&Args::parse(&args[2..]))?,
+        "config" => config(&Args::parse(&args[2..]))?,
+        "selfcheck" => selfcheck(&Args::parse(&args[2..]))?,
+        "hunt" => hunt(&Args::parse(&args[2..]))?,
         _ => {
             eprintln!("Unknown command: {}", args[1]);
             std::process::exit(1);
@@ -35,52 +60,524 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-fn play() -> Result<()> {
-    let mut play = Play::new();
+/// Applies the global `--threads N` option. Without it all cores are used.
+fn configure_threads(args: &Args) -> Result<()> {
+    if let Some(threads) = args.parse_value::<usize>("threads")? {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| anyhow!("Failed to configure {} threads: {}", threads, e))?;
+    }
+
+    Ok(())
+}
+
+/// Loads the solver config given with `--config` (`solver_config.json` by
+/// default) and applies the command line options overriding it.
+fn solver_config(args: &Args) -> Result<SolverConfig> {
+    load_config(args.value("config").unwrap_or(config::CONFIG_PATH), args)
+}
+
+fn load_config(path: &str, args: &Args) -> Result<SolverConfig> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path, e))?;
+    if SolverConfig::is_v1(&content) {
+        eprintln!(
+            "Note: {} uses the old weights-only format, run `wordle-bot config --migrate --config {}` to upgrade it",
+            path, path
+        );
+    }
+
+    let mut config = SolverConfig::parse(&content, path)?;
+    apply_overrides(args, &mut config)?;
+    Ok(config)
+}
+
+/// Command line options take precedence over the config file.
+fn apply_overrides(args: &Args, config: &mut SolverConfig) -> Result<()> {
+    if let Some(strategy) = args.parse_value("strategy")? {
+        config.strategy = strategy;
+    }
+    if args.flag("hard-mode") {
+        config.hard_mode = true;
+    }
+    if args.flag("live-stats") {
+        config.live_stats = true;
+    }
+    if let Some(top) = args.parse_value::<usize>("top-n")? {
+        config.top_n = top.max(1);
+    }
+
+    // Lookahead strategy
+    if let Some(base) = args.parse_value("lookahead-base")? {
+        if base == RankMethod::Lookahead {
+            return Err(anyhow!("--lookahead-base can't be lookahead itself"));
+        }
+        config.lookahead.base = base;
+    }
+    if let Some(top_k) = args.parse_value("lookahead-k")? {
+        config.lookahead.top_k = top_k;
+    }
+    if let Some(max_nodes) = args.parse_value("max-nodes")? {
+        config.lookahead.max_nodes = max_nodes;
+    }
+    if let Some(ms) = args.parse_value("time-limit")? {
+        config.lookahead.time_limit = Some(Duration::from_millis(ms));
+    }
+
+    // Exact endgame search, `--endgame 0` disables it
+    if let Some(threshold) = args.parse_value("endgame")? {
+        config.endgame.threshold = threshold;
+    }
+    if let Some(objective) = args.parse_value("endgame-objective")? {
+        config.endgame.objective = objective;
+    }
+
+    Ok(())
+}
+
+/// Builds the strategy selected in the config.
+fn strategy(config: &SolverConfig, options: &strategy::Options) -> Result<Box<dyn Strategy>> {
+    Ok(strategy::build(config.strategy, options)?)
+}
+
+/// Builds the strategy options from the config, the frequency prior and the
+/// used answers, which are made less likely if asked for.
+fn strategy_options(
+    args: &Args,
+    config: &SolverConfig,
+    used: Option<&(UsedAnswers, UsedMode)>,
+) -> Result<strategy::Options> {
+    let mut prior = prior(args)?;
+    if let Some((used, UsedMode::Downweight(factor))) = used {
+        let prior = prior.get_or_insert_with(Prior::uniform);
+        for (_, word) in used.entries() {
+            prior.downweight(word, *factor);
+        }
+    }
+
+    Ok(strategy::Options {
+        stats: LetterStats::load(STATS_PATH)?,
+        weights: config.weights.clone(),
+        live_stats: config.live_stats,
+        lookahead: config.lookahead,
+        prior: prior.map(Arc::new),
+    })
+}
+
+/// Leaves used answers out of a simulation when they are excluded.
+fn exclude_used(builder: &mut TreeBuilder, used: Option<&(UsedAnswers, UsedMode)>) {
+    if let Some((used, UsedMode::Exclude)) = used {
+        let words: Vec<&str> = used.entries().map(|(_, word)| word).collect();
+        builder.exclude_answers(&words);
+    }
+}
+
+/// Loads the used answers list given with `--used [file]`, and what to do with them.
+fn used_answers(args: &Args) -> Result<Option<(UsedAnswers, UsedMode)>> {
+    let path = match args.value("used") {
+        Some(path) => path,
+        None if args.flag("used") => used::USED_PATH,
+        None => return Ok(None),
+    };
+
+    let mut mode = args.parse_value("used-mode")?.unwrap_or(UsedMode::Exclude);
+    if let Some(factor) = args.parse_value::<f64>("used-weight")? {
+        let UsedMode::Downweight(weight) = &mut mode else {
+            return Err(anyhow!("--used-weight only applies with --used-mode downweight"));
+        };
+        if !(0.0..=1.0).contains(&factor) {
+            return Err(anyhow!("--used-weight must be between 0 and 1"));
+        }
+        *weight = factor;
+    }
+
+    Ok(Some((UsedAnswers::load(path)?, mode)))
+}
+
+/// Loads the word frequency file given with `--prior`, if any.
+fn prior(args: &Args) -> Result<Option<Prior>> {
+    let Some(path) = args.value("prior") else {
+        return Ok(None);
+    };
+
+    let mut config = PriorConfig::default();
+    if let Some(smoothing) = args.parse_value("smoothing")? {
+        config.smoothing = smoothing;
+    }
+    if let Some(center) = args.parse_value("prior-center")? {
+        config.center = center;
+    }
+    if let Some(width) = args.parse_value::<f64>("prior-width")? {
+        if width <= 0.0 {
+            return Err(anyhow!("--prior-width must be positive"));
+        }
+        config.width = width;
+    }
+
+    Ok(Some(Prior::load(path, &config)?))
+}
+
+fn play(args: &Args) -> Result<()> {
+    let used = used_answers(args)?;
+    let used = used.as_ref().map(|(used, mode)| (used, *mode));
+
+    let mut play = if args.flag("daily") || args.value("date").is_some() {
+        // Everyone gets the same word on the same date
+        let date = args.value("date").map_or_else(used::today, str::to_string);
+        let mut rng = StdRng::seed_from_u64(used::days_from_civil(&date)? as u64);
+        let words = wordlist::load_words()?;
+        // Only answers used before the date, so recording the date's own answer doesn't change it
+        let earlier = used.map(|(used, mode)| (used.before(&date), mode));
+        println!("Daily game for {}", date);
+        Play::with_answer(play::pick_answer(
+            wordlist::answers(&words),
+            earlier.as_ref().map(|(used, mode)| (used, *mode)),
+            &mut rng,
+        )?)?
+    } else if used.is_some() {
+        let words = wordlist::load_words()?;
+        Play::with_answer(play::pick_answer(wordlist::answers(&words), used, &mut rand::rng())?)?
+    } else {
+        Play::new()?
+    };
     play.run()?;
 
     Ok(())
 }
 
-fn solve() -> Result<()> {
-    let solver = Solver::new();
-    solver?.run()?;
+fn tune(args: &Args) -> Result<()> {
+    let mut config = TuneConfig::default();
+    if let Some(search) = args.parse_value("search")? {
+        config.search = search;
+    }
+    if let Some(iterations) = args.parse_value("iterations")? {
+        config.iterations = iterations;
+    }
+    if let Some(population) = args.parse_value::<usize>("population")? {
+        config.population = population.max(1);
+    }
+    if let Some(step) = args.parse_value::<f64>("step")? {
+        if !(step > 0.0 && step <= 1.0) {
+            return Err(anyhow!("--step must be between 0 and 1"));
+        }
+        config.step = step;
+    }
+    if let Some(seed) = args.parse_value("seed")? {
+        config.seed = seed;
+    }
+    let out = args.value("out").unwrap_or(config::CONFIG_PATH);
+    let report_path = args.value("report").unwrap_or("tune_report.md");
+    let opener = args.value("opener").map(|o| o.to_lowercase());
+
+    let mut solver_config = solver_config(args)?;
+    let used = used_answers(args)?;
+    let options = strategy_options(args, &solver_config, used.as_ref())?;
+
+    let words = wordlist::load_words()?;
+    let matrix = PatternMatrix::load_or_build(&words, wordlist::answers(&words))?;
+
+    // Simulate the solver the written config will run, including its endgame and hard mode
+    let mut tuner = Tuner::new(&matrix, solver_config.strategy, options, solver_config.endgame, opener.clone(), config)?;
+    tuner.set_hard_mode(solver_config.hard_mode);
+    if let Some((used, UsedMode::Exclude)) = &used {
+        tuner.exclude_answers(&used.entries().map(|(_, word)| word).collect::<Vec<_>>());
+    }
+    let result = tuner.run(&solver_config.weights, |result| {
+        println!(
+            "Iteration {}/{}: best average {:.4}, {} failures ({} configs evaluated)",
+            result.iterations, config.iterations, result.best.average, result.best.failures, result.evaluations
+        );
+    })?;
+
+    fs::write(report_path, tune::report(&result, &config, opener.as_deref()))?;
+    println!("Baseline: average {:.4}, worst {}, {} failures", result.baseline.average, result.baseline.max_guesses, result.baseline.failures);
+    println!("Best:     average {:.4}, worst {}, {} failures", result.best.average, result.best.max_guesses, result.best.failures);
+    if result.best.failures > 0 {
+        println!("No config without failures was found, try more iterations or a larger --endgame");
+    }
+
+    if result.improvements.is_empty() {
+        println!("No config beat the current weights, {} left unchanged", out);
+    } else {
+        if Path::new(out).exists() {
+            fs::copy(out, format!("{}.bak", out))?;
+        }
+        solver_config.weights = result.best.weights.clone();
+        fs::write(out, solver_config.to_json())?;
+        println!("Saved the best weights to {}", out);
+    }
+    println!("Saved the report to {}", report_path);
+
+    Ok(())
+}
+
+fn compare(args: &Args) -> Result<()> {
+    let usage = || anyhow!("Usage: wordle-bot compare --a <config> --b <config> [--opener <word>] [--strategy <name>] [--show <n>]");
+    let path_a = args.value("a").ok_or_else(usage)?;
+    let path_b = args.value("b").ok_or_else(usage)?;
+    let opener = args.value("opener").map(|o| o.to_lowercase());
+    let show: usize = args.parse_value("show")?.unwrap_or(10);
+
+    let words = wordlist::load_words()?;
+    let matrix = PatternMatrix::load_or_build(&words, wordlist::answers(&words))?;
+    let used = used_answers(args)?;
+
+    // Both configs play the same answers, with the command line options applied to both
+    let simulate = |path: &str| -> Result<std::collections::BTreeMap<String, usize>> {
+        let config = load_config(path, args)?;
+        let strategy = strategy(&config, &strategy_options(args, &config, used.as_ref())?)?;
+
+        let mut builder = TreeBuilder::new(&matrix, strategy.as_ref());
+        builder.set_endgame(config.endgame);
+        builder.set_hard_mode(config.hard_mode);
+        exclude_used(&mut builder, used.as_ref());
+        let opener = opener.as_deref().unwrap_or_else(|| builder.opener());
+        println!("Simulating {} with opener '{}'...", path, opener);
+        Ok(builder.build(opener)?.guesses_per_answer())
+    };
+
+    let comparison = compare::compare(&simulate(path_a)?, &simulate(path_b)?)?;
+    println!();
+    print!("{}", compare::report(&comparison, path_a, path_b, show));
+
+    Ok(())
+}
+
+/// Validates the solver config and prints it in the current format, or
+/// rewrites an old one in the current format with `--migrate`.
+fn config(args: &Args) -> Result<()> {
+    let path = args.value("config").unwrap_or(config::CONFIG_PATH);
+    let content = fs::read_to_string(path)
+        .map_err(|e| anyhow!("Failed to read {}: {}", path, e))?;
+    let config = SolverConfig::parse(&content, path)?;
+
+    if !args.flag("migrate") {
+        print!("{}", config.to_json());
+        return Ok(());
+    }
+
+    if !SolverConfig::is_v1(&content) {
+        println!("{} already uses version {} of the config format", path, config::CONFIG_VERSION);
+        return Ok(());
+    }
+    fs::write(format!("{}.bak", path), &content)?;
+    fs::write(path, config.to_json())?;
+    println!("Migrated {} to version {} (the old file is kept as {}.bak)", path, config::CONFIG_VERSION, path);
+
+    Ok(())
+}
+
+/// Checks the filter against scoring every word with `play::evaluate`: the
+/// documented edge cases, `--samples` random histories, and every single
+/// guess/answer pair with `--all`.
+fn selfcheck(args: &Args) -> Result<()> {
+    let samples: usize = args.parse_value("samples")?.unwrap_or(1000);
+    let max_lines: usize = args.parse_value("max-lines")?.unwrap_or(4);
+    let seed: u64 = args.parse_value("seed")?.unwrap_or(0);
+    let show: usize = args.parse_value("show")?.unwrap_or(10);
+
+    let words = wordlist::load_words()?;
+    let check = SelfCheck::new(&words)?;
+
+    let mut mismatches = Vec::new();
+    let mut run = |name: String, found: Vec<Mismatch>| {
+        match found.len() {
+            0 => println!("{}: ok", name),
+            n => println!("{}: {} mismatches", name, n),
+        }
+        mismatches.extend(found);
+    };
+
+    run("Edge cases of docs/Solver.md".to_string(), check.check_known()?);
+    if samples > 0 {
+        let name = format!("{} random histories of 1-{} guesses (seed {})", samples, max_lines, seed);
+        run(name, check.check_random(samples, max_lines, seed)?);
+    }
+    if args.flag("all") {
+        println!("Checking every guess against every answer, this takes a while...");
+        run("Every guess/answer pair".to_string(), check.check_all_pairs()?);
+    }
+
+    if mismatches.is_empty() {
+        println!("The filter agrees with evaluate on every checked history");
+        return Ok(());
+    }
+
+    // Shortest counterexamples first
+    mismatches.sort_by_key(|mismatch| mismatch.lines.len());
+    for (i, mismatch) in mismatches.iter().take(show).enumerate() {
+        println!();
+        print!("{}. {}", i + 1, mismatch);
+    }
+    if mismatches.len() > show {
+        println!();
+        println!("...and {} more (use --show to list more)", mismatches.len() - show);
+    }
+
+    Err(anyhow!("The filter disagrees with evaluate on {} words", mismatches.len()))
+}
+
+/// Plays the solver against every answer and writes the games that need more
+/// than `--max-guesses`, lose the answer to the filter or aren't solved to a report.
+fn hunt(args: &Args) -> Result<()> {
+    let opener = args
+        .value("opener")
+        .ok_or_else(|| anyhow!("Usage: wordle-bot hunt --opener <word> [--max-guesses <n>] [--all-words] [--report <file>] [--strategy <name>]"))?
+        .to_lowercase();
+    let report_path = args.value("report").unwrap_or("edge_cases.md");
+    let solver_config = solver_config(args)?;
+    let used = used_answers(args)?;
+    let strategy = strategy(&solver_config, &strategy_options(args, &solver_config, used.as_ref())?)?;
+
+    let mut config = HuntConfig {
+        top_n: solver_config.top_n,
+        hard_mode: solver_config.hard_mode,
+        endgame: solver_config.endgame,
+        all_words: args.flag("all-words"),
+        ..HuntConfig::default()
+    };
+    if let Some(max_guesses) = args.parse_value("max-guesses")? {
+        config.max_guesses = max_guesses;
+    }
+
+    let words = wordlist::load_words()?;
+    println!("Playing every answer with opener '{}', candidates: {}...", opener, config.candidates_label());
+    let mut hunter = Hunter::new(&words, strategy.as_ref(), config.clone())?;
+    if let Some((used, UsedMode::Exclude)) = &used {
+        hunter.exclude(used.entries().map(|(_, word)| word));
+    }
+    let result = hunter.hunt(&opener)?;
+
+    fs::write(report_path, hunt::report(&result, &opener, &config))?;
+
+    let count = |outcome| result.cases.iter().filter(|case| case.outcome == outcome).count();
+    println!("Answers solved: {}", result.guesses.len());
+    if !result.guesses.is_empty() {
+        let total: usize = result.guesses.values().sum();
+        println!("Average guesses: {:.4}", total as f64 / result.guesses.len() as f64);
+    }
+    println!("More than {} guesses: {}", config.max_guesses, count(hunt::Outcome::TooManyGuesses));
+    println!("Answer filtered out: {}", count(hunt::Outcome::AnswerFilteredOut));
+    println!("Not solved: {}", count(hunt::Outcome::Unsolved));
+    println!("Saved the report to {}", report_path);
+
+    Ok(())
+}
+
+/// Lists the used answers, or records today's (or `--date`'s) answer with `--add`.
+fn used(args: &Args) -> Result<()> {
+    let path = args.value("used").unwrap_or(used::USED_PATH);
+    let mut used = UsedAnswers::load_or_default(path)?;
+
+    let Some(word) = args.value("add") else {
+        for (date, word) in used.entries() {
+            println!("{} {}", date, word);
+        }
+        return Ok(());
+    };
+
+    let word = word.to_lowercase();
+    let words = wordlist::load_words()?;
+    if !words.contains(&word) {
+        return Err(anyhow!("'{}' is not in the wordlist", word));
+    }
+
+    let date = args.value("date").map_or_else(used::today, str::to_string);
+    if used.append(path, &date, &word)? {
+        println!("Recorded '{}' as the answer of {} in {}", word, date, path);
+    } else {
+        println!("'{}' is already recorded for {}", word, date);
+    }
+
+    Ok(())
+}
+
+fn solve(args: &Args) -> Result<()> {
+    let config = solver_config(args)?;
+    let used = used_answers(args)?;
+    let options = strategy_options(args, &config, used.as_ref())?;
+    let mut solver = Solver::new(strategy(&config, &options)?)?;
+    if let Some(prior) = options.prior {
+        solver.set_prior(prior);
+    }
+    if let Some((used, UsedMode::Exclude)) = &used {
+        solver.exclude(used.entries().map(|(_, word)| word));
+    }
+    solver.set_endgame(config.endgame);
+    solver.set_hard_mode(config.hard_mode);
+    solver.set_top_n(config.top_n);
+
+    let history = match (args.value("history"), args.value("history-file")) {
+        (Some(text), _) => Some(game::parse_history(text)?),
+        (None, Some(path)) => Some(game::load_history(path)?),
+        (None, None) => None,
+    };
+    let Some(history) = history else {
+        if let Some(path) = args.value("tree") {
+            solver.load_tree(path)?;
+        }
+        solver.run()?;
+        return Ok(());
+    };
+    if args.value("tree").is_some() {
+        return Err(anyhow!("--tree can't be combined with --history, which doesn't follow a decision tree"));
+    }
+
+    solve_history(&mut solver, &history, args.flag("json"))
+}
+
+/// Enters the lines of a history, then prints the suggestions and remaining
+/// candidates once instead of asking for more guesses.
+fn solve_history(solver: &mut Solver, history: &[(String, Pattern)], json: bool) -> Result<()> {
+    for (word, pattern) in history {
+        if let Some(contradiction) = solver.add_line(word, pattern)? {
+            let fixes: Vec<String> = contradiction.fixes.iter().map(|fix| fix.pattern.to_string()).collect();
+            let hint = if fixes.is_empty() {
+                String::new()
+            } else {
+                format!(" (patterns that would fit: {})", fixes.join(", "))
+            };
+            return Err(anyhow!("{} {}: {}{}", word, pattern, contradiction, hint));
+        }
+    }
+
+    let suggestions = solver.suggestions();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&suggestions)?);
+        return Ok(());
+    }
+
+    if suggestions.solved {
+        println!("Solved: the word is '{}'.", suggestions.candidates.first().map_or("", String::as_str));
+        return Ok(());
+    }
+    solver.print_suggestions(&suggestions);
+    println!("Remaining candidates:");
+    for row in suggestions.candidates.chunks(10) {
+        println!("{}", row.join(" "));
+    }
 
     Ok(())
 }
 
 fn analyze() -> Result<()> {
-    let content = fs::read_to_string("wordlist.txt")?;
+    let content = fs::read_to_string(wordlist::WORDLIST_PATH)?;
     let words: Vec<&str> = content.lines().collect();
     let stats = LetterStats::from_words(&words);
 
-    let mut json = serde_json::to_string_pretty(&stats)?;
-
-    // This regex joins lines between '[' and ']'
-    let re = regex::Regex::new(r"\[\s*((?:\d+,\s*)*\d+)\s*\]").unwrap();
-    json = re
-        .replace_all(&json, |caps: &regex::Captures| {
-            let inner = caps[1]
-                .split_whitespace()
-                .collect::<Vec<_>>()
-                .join(" ");
-            format!("[{}]", inner.replace(", ", ", "))
-        })
-        .to_string();
-
-    fs::write("letter_stats.json", json)?;
-    println!("Saved letter stats to letter_stats.json");
+    fs::write(STATS_PATH, stats.to_json())?;
+    println!("Saved letter stats to {}", STATS_PATH);
 
     Ok(())
 }
 
 fn rank() -> Result<()> {
-    use ranking::rank_words;
-    let content = fs::read_to_string("wordlist.txt")?;
+    let content = fs::read_to_string(wordlist::WORDLIST_PATH)?;
     let words: Vec<&str> = content.lines().collect();
 
-    let stats_json = fs::read_to_string("letter_stats.json")?;
-    let results = rank_words(&words, &stats_json)?;
+    let stats = LetterStats::load(STATS_PATH)?;
+    let results = ranking::rank_words(&words, &stats);
 
     println!("Top 10 words by letter position frequency:");
     for (word, score) in results.iter().take(10) {
@@ -89,3 +586,133 @@ fn rank() -> Result<()> {
 
     Ok(())
 }
+
+fn tree(args: &Args) -> Result<()> {
+    let opener = args
+        .value("opener")
+        .ok_or_else(|| anyhow!("Usage: wordle-bot tree --opener <word> [--out <prefix>] [--strategy <name>]"))?
+        .to_lowercase();
+    let prefix = args.value("out").unwrap_or("tree");
+    let config = solver_config(args)?;
+    let used = used_answers(args)?;
+    let strategy = strategy(&config, &strategy_options(args, &config, used.as_ref())?)?;
+
+    let words = wordlist::load_words()?;
+    let matrix = PatternMatrix::load_or_build(&words, wordlist::answers(&words))?;
+
+    let mut builder = TreeBuilder::new(&matrix, strategy.as_ref());
+    exclude_used(&mut builder, used.as_ref());
+    builder.set_endgame(config.endgame);
+    builder.set_hard_mode(config.hard_mode);
+    let root = builder.build(&opener)?;
+
+    let json_path = format!("{}.json", prefix);
+    let text_path = format!("{}.txt", prefix);
+    fs::write(&json_path, serde_json::to_string_pretty(&root)?)?;
+    fs::write(&text_path, root.to_text())?;
+
+    let summary = root.stats();
+    println!("Decision tree for opener '{}':", opener);
+    println!("Answers:         {}", summary.answers);
+    println!("Average guesses: {:.4}", summary.average());
+    println!("Worst case:      {}", summary.max_guesses);
+    println!("Failures (>6):   {}", summary.failures);
+    println!("Saved tree to {} and {}", json_path, text_path);
+
+    Ok(())
+}
+
+fn optimal(args: &Args) -> Result<()> {
+    let opener = args
+        .value("opener")
+        .ok_or_else(|| anyhow!("Usage: wordle-bot optimal --opener <word> [--out <prefix>] [--objective <expected|worst>] [--breadth <n>]"))?
+        .to_lowercase();
+    let prefix = args.value("out").unwrap_or("optimal");
+    let objective = args.parse_value("objective")?.unwrap_or(Objective::Expected);
+    let breadth: Option<usize> = args.parse_value("breadth")?;
+
+    let words = wordlist::load_words()?;
+    let matrix = PatternMatrix::load_or_build(&words, wordlist::answers(&words))?;
+
+    // Numbered under the lock, so the lines come out in order whichever bucket finishes first
+    let solved = Mutex::new(0);
+    let (score, root) = optimal::optimal_tree(&matrix, &opener, objective, breadth, |bucket| {
+        let mut solved = solved.lock().unwrap_or_else(|e| e.into_inner());
+        *solved += 1;
+        println!(
+            "Solved {}/{}: {} ({} answers), cost {}",
+            *solved, bucket.buckets, bucket.pattern, bucket.answers, bucket.cost
+        );
+    })?;
+
+    let json_path = format!("{}.json", prefix);
+    let text_path = format!("{}.txt", prefix);
+    fs::write(&json_path, serde_json::to_string_pretty(&root)?)?;
+    fs::write(&text_path, root.to_text())?;
+
+    let summary = root.stats();
+    println!("Optimal policy for opener '{}' ({}):", opener, objective.label());
+    println!("Answers:         {}", summary.answers);
+    println!("Average guesses: {:.4}", summary.average());
+    println!("Worst case:      {}", summary.max_guesses);
+    println!("Failures (>6):   {}", summary.failures);
+    match breadth {
+        Some(n) => println!("Search limited to {} splits per set: {:.4} is an upper bound, not the optimum", n, score),
+        None => println!("Proven optimum:  {:.4}", score),
+    }
+    println!("Saved tree to {} and {}", json_path, text_path);
+
+    Ok(())
+}
+
+fn openers(args: &Args) -> Result<()> {
+    let shortlist: Option<usize> = args.parse_value("top")?;
+    let show: usize = args.parse_value("show")?.unwrap_or(20);
+    let config = solver_config(args)?;
+    let used = used_answers(args)?;
+    let strategy = strategy(&config, &strategy_options(args, &config, used.as_ref())?)?;
+
+    let words = wordlist::load_words()?;
+    let word_refs: Vec<&str> = words.iter().map(|s| s.as_str()).collect();
+    let matrix = PatternMatrix::load_or_build(&words, wordlist::answers(&words))?;
+
+    let stats = LetterStats::load(STATS_PATH)?;
+
+    // Either every allowed guess, or the best N by the frequency heuristic
+    let candidates: Vec<String> = match shortlist {
+        Some(n) => ranking::rank_words(&word_refs, &stats)
+            .into_iter()
+            .take(n)
+            .map(|(word, _)| word)
+            .collect(),
+        None => words.clone(),
+    };
+    let candidate_refs: Vec<&str> = candidates.iter().map(|s| s.as_str()).collect();
+
+    println!("Evaluating {} openers over {} answers...", candidate_refs.len(), matrix.answers().len());
+    let mut builder = TreeBuilder::new(&matrix, strategy.as_ref());
+    exclude_used(&mut builder, used.as_ref());
+    builder.set_hard_mode(config.hard_mode);
+    // The exact endgame makes every simulation much slower, so screening
+    // openers only uses it when asked for
+    let mut endgame = config.endgame;
+    if args.value("endgame").is_none() {
+        endgame.threshold = 0;
+    }
+    builder.set_endgame(endgame);
+    let results = openers::evaluate_openers(&candidate_refs, &builder)?;
+
+    println!("{:<6} {:<8} {:>9} {:>6} {:>9}", "Rank", "Word", "Average", "Worst", "Failures");
+    for (rank, result) in results.iter().take(show).enumerate() {
+        println!(
+            "{:<6} {:<8} {:>9.4} {:>6} {:>9}",
+            rank + 1,
+            result.word,
+            result.average,
+            result.max_guesses,
+            result.failures
+        );
+    }
+
+    Ok(())
+}
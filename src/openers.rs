@@ -0,0 +1,39 @@
+use crate::tree::TreeBuilder;
+use crate::error::Result;
+use rayon::prelude::*;
+
+/// How the solver performs over the whole answer list when starting with `word`.
+#[derive(Debug, Clone)]
+pub struct OpenerResult {
+    pub word: String,
+    pub average: f64,
+    pub max_guesses: usize,
+    pub failures: usize,
+}
+
+/// Simulates the solver over all answers for every opener and returns the
+/// results best first: fewest failures, then lowest average, then lowest worst case.
+pub fn evaluate_openers(openers: &[&str], builder: &TreeBuilder) -> Result<Vec<OpenerResult>> {
+    let mut results: Vec<OpenerResult> = openers
+        .par_iter()
+        .map(|&word| {
+            let stats = builder.build(word)?.stats();
+            Ok(OpenerResult {
+                word: word.to_string(),
+                average: stats.average(),
+                max_guesses: stats.max_guesses,
+                failures: stats.failures,
+            })
+        })
+        .collect::<Result<_>>()?;
+
+    results.sort_by(|a, b| {
+        a.failures
+            .cmp(&b.failures)
+            .then(a.average.partial_cmp(&b.average).unwrap())
+            .then(a.max_guesses.cmp(&b.max_guesses))
+            .then(a.word.cmp(&b.word))
+    });
+
+    Ok(results)
+}
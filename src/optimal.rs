@@ -0,0 +1,90 @@
+use crate::endgame::{Endgame, Objective, Plan};
+use crate::game::Pattern;
+use crate::patterns::{decode, PatternMatrix, SOLVED_CODE};
+use crate::tree::TreeNode;
+use crate::error::{Error, Result};
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+/// A bucket of the opener that `optimal_tree` has finished searching.
+#[derive(Debug, Clone, Copy)]
+pub struct SolvedBucket {
+    pub pattern: Pattern,
+    pub answers: usize,
+    pub cost: u32,
+    /// Number of buckets the opener leaves in total.
+    pub buckets: usize,
+}
+
+/// Computes the optimal strategy over all answers after a fixed opener, by
+/// running the exact search on every bucket the opener leaves. The buckets
+/// are searched in parallel, each on a single thread, and `progress` is
+/// called as each one finishes. Returns the average (or worst-case) number
+/// of guesses and the decision tree.
+pub fn optimal_tree(
+    matrix: &PatternMatrix,
+    opener: &str,
+    objective: Objective,
+    breadth: Option<usize>,
+    progress: impl Fn(&SolvedBucket) + Sync,
+) -> Result<(f64, TreeNode)> {
+    let opener_index = matrix
+        .guess_index(opener)
+        .ok_or_else(|| Error::UnknownWord(opener.to_string()))?;
+
+    let row = matrix.row(opener_index);
+    let mut is_answer = false;
+    let mut buckets: BTreeMap<u8, Vec<usize>> = BTreeMap::new();
+    for (answer, &code) in row.iter().enumerate() {
+        if code == SOLVED_CODE {
+            is_answer = true;
+        } else {
+            buckets.entry(code).or_default().push(answer);
+        }
+    }
+
+    let pattern = |g: usize, a: usize| matrix.row(g)[a];
+    let count = buckets.len();
+    let solved: Vec<(u8, u32, Plan)> = buckets
+        .into_par_iter()
+        .map(|(code, bucket)| {
+            let mut endgame = Endgame::new(matrix.guesses().len(), objective, pattern);
+            endgame.set_breadth(breadth);
+            let (cost, plan) = endgame.solve(&bucket);
+            progress(&SolvedBucket { pattern: decode(code), answers: bucket.len(), cost, buckets: count });
+            (code, cost, plan)
+        })
+        .collect();
+
+    let answers = matrix.answers().len();
+    let score = match objective {
+        Objective::Expected => {
+            // Every answer needs the opener, plus what its bucket costs
+            let total: u32 = answers as u32 + solved.iter().map(|(_, cost, _)| cost).sum::<u32>();
+            total as f64 / answers as f64
+        }
+        Objective::WorstCase => {
+            let deepest = solved.iter().map(|(_, cost, _)| *cost).max().unwrap_or(0);
+            1.0 + deepest as f64
+        }
+    };
+
+    let children = solved
+        .into_iter()
+        .map(|(code, _, plan)| (decode(code), to_tree(matrix, &plan)))
+        .collect();
+
+    Ok((score, TreeNode { guess: opener.to_string(), is_answer, children }))
+}
+
+fn to_tree(matrix: &PatternMatrix, plan: &Plan) -> TreeNode {
+    TreeNode {
+        guess: matrix.guesses()[plan.guess].clone(),
+        is_answer: plan.solves,
+        children: plan
+            .children
+            .iter()
+            .map(|(code, child)| (decode(*code), to_tree(matrix, child)))
+            .collect(),
+    }
+}
@@ -0,0 +1,242 @@
+use crate::error::{self, Error, Result};
+use crate::game::Pattern;
+use memmap2::Mmap;
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::fs::{self, File};
+
+/// Where the guess×answer matrix is cached between runs.
+pub const CACHE_PATH: &str = "pattern_matrix.bin";
+
+const MAGIC: &[u8; 4] = b"WBPM";
+const VERSION: u32 = 1;
+const HEADER_LEN: usize = 24;
+
+/// Code of the all-correct pattern `ccccc`.
+pub const SOLVED_CODE: u8 = 242;
+
+/// Scores `guess` against `answer` like `play::evaluate`, but without allocating.
+/// The pattern is returned as a base-3 number with `w = 0`, `m = 1`, `c = 2`
+/// and the first letter as the lowest digit, so every pattern fits in a `u8`.
+pub fn pattern_code(guess: &[u8], answer: &[u8]) -> u8 {
+    let mut remaining = [0u8; 26];
+    let mut states = [0u8; 5];
+
+    // First pass: correct positions, count the rest of the answer's letters
+    for i in 0..5 {
+        if guess[i] == answer[i] {
+            states[i] = 2;
+        } else {
+            remaining[(answer[i] - b'a') as usize] += 1;
+        }
+    }
+
+    // Second pass: misplaced letters consume the remaining counts
+    for i in 0..5 {
+        if states[i] == 0 {
+            let count = &mut remaining[(guess[i] - b'a') as usize];
+            if *count > 0 {
+                states[i] = 1;
+                *count -= 1;
+            }
+        }
+    }
+
+    states.iter().rev().fold(0, |code, &state| code * 3 + state)
+}
+
+/// Converts a pattern code back into its pattern.
+pub fn decode(code: u8) -> Pattern {
+    Pattern::from_code(code)
+}
+
+enum Storage {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+/// The feedback pattern of every allowed guess against every answer, stored
+/// row-major (one row of answers per guess) as pattern codes.
+pub struct PatternMatrix {
+    guesses: Vec<String>,
+    answers: Vec<String>,
+    guess_index: HashMap<String, usize>,
+    storage: Storage,
+}
+
+impl PatternMatrix {
+    /// Memory-maps the cached matrix if it matches these word lists, otherwise
+    /// computes it and writes the cache for the next run.
+    pub fn load_or_build(guesses: &[String], answers: &[String]) -> Result<Self> {
+        let key = word_list_hash(guesses, answers);
+
+        let storage = match map_cache(key, guesses.len(), answers.len()) {
+            Some(mmap) => Storage::Mapped(mmap),
+            None => {
+                let data = compute(guesses, answers);
+                match write_cache(key, guesses.len(), answers.len(), &data) {
+                    Ok(()) => match map_cache(key, guesses.len(), answers.len()) {
+                        Some(mmap) => Storage::Mapped(mmap),
+                        None => Storage::Owned(data),
+                    },
+                    Err(e) => {
+                        eprintln!("Warning: could not write {}: {}", CACHE_PATH, e);
+                        Storage::Owned(data)
+                    }
+                }
+            }
+        };
+
+        let guess_index = guesses
+            .iter()
+            .enumerate()
+            .map(|(i, word)| (word.clone(), i))
+            .collect();
+
+        Ok(Self {
+            guesses: guesses.to_vec(),
+            answers: answers.to_vec(),
+            guess_index,
+            storage,
+        })
+    }
+
+    pub fn guesses(&self) -> &[String] {
+        &self.guesses
+    }
+
+    pub fn answers(&self) -> &[String] {
+        &self.answers
+    }
+
+    pub fn guess_index(&self, word: &str) -> Option<usize> {
+        self.guess_index.get(word).copied()
+    }
+
+    /// Patterns of one guess against every answer.
+    pub fn row(&self, guess: usize) -> &[u8] {
+        let start = guess * self.answers.len();
+        &self.data()[start..start + self.answers.len()]
+    }
+
+    fn data(&self) -> &[u8] {
+        match &self.storage {
+            Storage::Mapped(mmap) => &mmap[HEADER_LEN..],
+            Storage::Owned(data) => data,
+        }
+    }
+}
+
+fn compute(guesses: &[String], answers: &[String]) -> Vec<u8> {
+    let mut data = vec![0u8; guesses.len() * answers.len()];
+    if answers.is_empty() {
+        return data;
+    }
+
+    data.par_chunks_mut(answers.len())
+        .zip(guesses.par_iter())
+        .for_each(|(row, guess)| {
+            for (cell, answer) in row.iter_mut().zip(answers) {
+                *cell = pattern_code(guess.as_bytes(), answer.as_bytes());
+            }
+        });
+    data
+}
+
+/// FNV-1a over both word lists, so the cache is rebuilt whenever `wordlist.txt` changes.
+fn word_list_hash(guesses: &[String], answers: &[String]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let mut feed = |bytes: &[u8]| {
+        for &b in bytes {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+
+    for word in guesses {
+        feed(word.as_bytes());
+        feed(b"\n");
+    }
+    feed(b"|");
+    for word in answers {
+        feed(word.as_bytes());
+        feed(b"\n");
+    }
+
+    hash
+}
+
+fn header(key: u64, guesses: usize, answers: usize) -> Vec<u8> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&key.to_le_bytes());
+    header.extend_from_slice(&(guesses as u32).to_le_bytes());
+    header.extend_from_slice(&(answers as u32).to_le_bytes());
+    header
+}
+
+fn write_cache(key: u64, guesses: usize, answers: usize, data: &[u8]) -> Result<()> {
+    let mut content = header(key, guesses, answers);
+    content.extend_from_slice(data);
+
+    // Write next to the cache and rename, so a concurrent run never maps a half
+    // written file. The name is per process, so two runs never share a temp file.
+    let tmp_path = format!("{}.{}.tmp", CACHE_PATH, std::process::id());
+    error::write_file(&tmp_path, content)?;
+    fs::rename(&tmp_path, CACHE_PATH).map_err(|source| Error::Io { path: CACHE_PATH.to_string(), source })?;
+
+    Ok(())
+}
+
+/// Maps the cache file if it exists and its header matches the expected lists.
+fn map_cache(key: u64, guesses: usize, answers: usize) -> Option<Mmap> {
+    let file = File::open(CACHE_PATH).ok()?;
+
+    // SAFETY: the cache is only ever replaced by renaming a new file over it,
+    // never modified in place, so the mapped contents can't change under us.
+    let mmap = unsafe { Mmap::map(&file) }.ok()?;
+
+    let valid = mmap.len() == HEADER_LEN + guesses * answers
+        && mmap[..HEADER_LEN] == header(key, guesses, answers)[..];
+
+    valid.then_some(mmap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::play::evaluate;
+
+    /// Words with repeated letters in the guess, the answer or both.
+    const WORDS: [&str; 20] = [
+        "speed", "abide", "erase", "steal", "crepe", "eerie", "llama", "hello", "allel", "alley",
+        "sassy", "salsa", "sally", "lathe", "table", "abhor", "aroid", "geese", "eject", "tepee",
+    ];
+
+    #[test]
+    fn pattern_code_agrees_with_evaluate() {
+        for guess in WORDS {
+            for answer in WORDS {
+                let expected = evaluate(guess, answer).pattern();
+                let code = pattern_code(guess.as_bytes(), answer.as_bytes());
+                assert_eq!(Pattern::from_code(code), expected, "{} against {}", guess, answer);
+                assert_eq!(code, expected.code(), "{} against {}", guess, answer);
+            }
+        }
+    }
+
+    #[test]
+    fn repeated_letters() {
+        let pattern = |guess: &str, answer: &str| decode(pattern_code(guess.as_bytes(), answer.as_bytes())).to_string();
+
+        // Only as many copies are marked as the answer has, greens first
+        assert_eq!(pattern("speed", "abide"), "wwmwm");
+        assert_eq!(pattern("allel", "alley"), "ccccw");
+        assert_eq!(pattern("eerie", "geese"), "mcwwc");
+        assert_eq!(pattern("salad", "salsa"), "cccmw");
+        assert_eq!(pattern("salet", "salet"), "ccccc");
+        assert_eq!(decode(SOLVED_CODE), Pattern::SOLVED);
+        assert_eq!(pattern_code(b"salet", b"salet"), SOLVED_CODE);
+    }
+}
@@ -1,10 +1,10 @@
-use std::fs;
-use rand::seq::SliceRandom;
-use rand::thread_rng;
 use rand::prelude::IndexedRandom;
-use crate::game::{GameData, CellData, LineData};
+use rand::Rng;
+use crate::error::{Error, Result};
+use crate::game::{CellData, CellState, GameData, LineData, Pattern};
+use crate::used::{UsedAnswers, UsedMode};
+use crate::wordlist;
 use std::collections::HashMap;
-use anyhow::Result;
 use std::io;
 
 pub struct Play {
@@ -23,26 +23,27 @@ pub enum GameResult {
 }
 
 impl Play {
-    pub fn new() -> Self {
-        let content = fs::read_to_string("wordlist.txt")
-            .expect("Failed to read wordlist.txt");
+    /// A game with a random answer.
+    pub fn new() -> Result<Self> {
+        let words = wordlist::load_words()?;
+        let random_word = pick_answer(wordlist::answers(&words), None, &mut rand::rng())?;
 
-        let words: Vec<String> = content
-            .lines()
-            .map(|w| w.trim().to_lowercase())
-            .filter(|w| w.len() == 5)
-            .collect();
+        Ok(Self::with_words(random_word, words))
+    }
 
-        let allowed_words = &words[10657..];
+    /// A game with a fixed answer, e.g. the daily word.
+    pub fn with_answer(word: String) -> Result<Self> {
+        let words = wordlist::load_words()?;
+        if !words.contains(&word) {
+            return Err(Error::UnknownWord(word));
+        }
 
-        let mut rng = thread_rng();
-        let random_word = allowed_words
-            .choose(&mut rng)
-            .expect("No words available")
-            .clone();
+        Ok(Self::with_words(word, words))
+    }
 
+    fn with_words(word: String, words: Vec<String>) -> Self {
         Self {
-            word: random_word,
+            word,
             num_guesses: 6,
             game_data: GameData::new(),
             wordlist: words,
@@ -51,58 +52,7 @@ impl Play {
     }
 
     pub fn evaluate_word(&mut self, guessed_word: &str) -> LineData {
-        let guessed_chars: Vec<char> = guessed_word.chars().collect();
-        let target_chars: Vec<char> = self.word.chars().collect();
-
-        let mut result_cells: [CellData; 5] = [
-            CellData { letter: ' ', state: 'w' },
-            CellData { letter: ' ', state: 'w' },
-            CellData { letter: ' ', state: 'w' },
-            CellData { letter: ' ', state: 'w' },
-            CellData { letter: ' ', state: 'w' },
-        ];
-
-        // Count remaining letters in target for handling duplicates
-        let mut remaining_counts: HashMap<char, usize> = HashMap::new();
-        for &c in &target_chars {
-            *remaining_counts.entry(c).or_insert(0) += 1;
-        }
-
-        // First pass: correct positions
-        for i in 0..5 {
-            let g = guessed_chars[i];
-            let t = target_chars[i];
-
-            if g == t {
-                result_cells[i] = CellData { letter: g, state: 'c' };
-                *remaining_counts.get_mut(&g).unwrap() -= 1;
-            } else {
-                result_cells[i].letter = g;
-            }
-        }
-
-        // Second pass: misplaced or wrong
-        for i in 0..5 {
-            if result_cells[i].state == 'c' {
-                continue;
-            }
-            let g = guessed_chars[i];
-            if let Some(count) = remaining_counts.get_mut(&g) {
-                if *count > 0 {
-                    result_cells[i].state = 'm';
-                    *count -= 1;
-                } else {
-                    result_cells[i].state = 'w';
-                }
-            } else {
-                result_cells[i].state = 'w';
-            }
-        }
-
-        LineData {
-            word: guessed_word.to_string(),
-            cells: result_cells,
-        }
+        evaluate(guessed_word, &self.word)
     }
 
     pub fn run(&mut self) -> Result<()> {
@@ -176,7 +126,9 @@ impl Play {
             let line = self.evaluate_word(&word);
             let pattern = self.get_pattern(&line);
 
-            self.game_data.add_line(&word, &pattern);
+            self.game_data
+                .add_line(&word, &pattern)
+                .expect("guesses are checked against the word list");
             break;
         }
     }
@@ -192,10 +144,9 @@ impl Play {
                 let letter = cell.letter.to_ascii_uppercase();
 
                 let color = match cell.state {
-                    'c' => "\x1b[42m\x1b[30m",  // green background, black text
-                    'm' => "\x1b[43m\x1b[30m",  // yellow background, black text
-                    'w' => "\x1b[100m\x1b[37m", // gray background, white text
-                    _ => "\x1b[0m",
+                    CellState::Correct => "\x1b[42m\x1b[30m",  // green background, black text
+                    CellState::Misplaced => "\x1b[43m\x1b[30m",  // yellow background, black text
+                    CellState::Wrong => "\x1b[100m\x1b[37m", // gray background, white text
                 };
 
                 print!("{} {} \x1b[0m", color, letter);
@@ -205,12 +156,81 @@ impl Play {
         println!("==========================\n");
     }
 
-    fn get_pattern(&self, line: &LineData) -> String {
-        line.cells.iter().map(|cell| cell.state).collect()
+    fn get_pattern(&self, line: &LineData) -> Pattern {
+        line.pattern()
     }
+}
 
-    fn reset(&mut self) {
-        self.word = String::new();
-        self.game_data.reset()
+/// Picks an answer at random, skipping used answers or making them less likely.
+pub fn pick_answer(answers: &[String], used: Option<(&UsedAnswers, UsedMode)>, rng: &mut impl Rng) -> Result<String> {
+    let weight = |word: &String| match used {
+        Some((used, mode)) if used.contains(word) => match mode {
+            UsedMode::Exclude => 0.0,
+            UsedMode::Downweight(factor) => factor,
+        },
+        _ => 1.0,
+    };
+
+    answers
+        .choose_weighted(rng, weight)
+        .cloned()
+        .map_err(|_| Error::NoAnswers)
+}
+
+/// Scores `guessed_word` against `target` the way Wordle does, handling
+/// duplicate letters by consuming the target's letter counts.
+pub fn evaluate(guessed_word: &str, target: &str) -> LineData {
+    let guessed_chars: Vec<char> = guessed_word.chars().collect();
+    let target_chars: Vec<char> = target.chars().collect();
+
+    let mut result_cells: [CellData; 5] = [
+        CellData { letter: ' ', state: CellState::Wrong },
+        CellData { letter: ' ', state: CellState::Wrong },
+        CellData { letter: ' ', state: CellState::Wrong },
+        CellData { letter: ' ', state: CellState::Wrong },
+        CellData { letter: ' ', state: CellState::Wrong },
+    ];
+
+    // Count remaining letters in target for handling duplicates
+    let mut remaining_counts: HashMap<char, usize> = HashMap::new();
+    for &c in &target_chars {
+        *remaining_counts.entry(c).or_insert(0) += 1;
+    }
+
+    // First pass: correct positions
+    for i in 0..5 {
+        let g = guessed_chars[i];
+        let t = target_chars[i];
+
+        if g == t {
+            result_cells[i] = CellData { letter: g, state: CellState::Correct };
+            *remaining_counts.get_mut(&g).unwrap() -= 1;
+        } else {
+            result_cells[i].letter = g;
+        }
+    }
+
+    // Second pass: misplaced or wrong
+    for i in 0..5 {
+        if result_cells[i].state == CellState::Correct {
+            continue;
+        }
+        let g = guessed_chars[i];
+        if let Some(count) = remaining_counts.get_mut(&g) {
+            if *count > 0 {
+                result_cells[i].state = CellState::Misplaced;
+                *count -= 1;
+            } else {
+                result_cells[i].state = CellState::Wrong;
+            }
+        } else {
+            result_cells[i].state = CellState::Wrong;
+        }
+    }
+
+    LineData {
+        word: guessed_word.to_string(),
+        cells: result_cells,
     }
 }
+
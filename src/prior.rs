@@ -0,0 +1,140 @@
+use crate::error::{self, Error, Result};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Weight of words that are missing from the frequency file or smoothed to
+/// nothing, so every word stays possible.
+const MIN_WEIGHT: f64 = 1e-6;
+
+/// How raw word frequencies are turned into weights between 0 and 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Smoothing {
+    /// Proportional to the frequency.
+    Linear,
+    /// Proportional to the logarithm of the frequency, flattening the head.
+    Log,
+    /// Close to 1 for the `center` most frequent words and close to 0 after,
+    /// with a transition about `width` words wide.
+    Sigmoid,
+}
+
+impl FromStr for Smoothing {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "log" => Ok(Self::Log),
+            "sigmoid" => Ok(Self::Sigmoid),
+            _ => Err(Error::UnknownName { kind: "smoothing", name: s.to_string(), expected: "linear, log or sigmoid" }),
+        }
+    }
+}
+
+/// Settings for turning a frequency file into a prior.
+#[derive(Debug, Clone, Copy)]
+pub struct PriorConfig {
+    pub smoothing: Smoothing,
+    /// Frequency rank where the sigmoid crosses 0.5.
+    pub center: usize,
+    /// Number of ranks the sigmoid takes to go from about 0.73 to 0.27.
+    pub width: f64,
+}
+
+impl Default for PriorConfig {
+    fn default() -> Self {
+        Self {
+            smoothing: Smoothing::Sigmoid,
+            center: 3000,
+            width: 500.0,
+        }
+    }
+}
+
+/// How likely every word is to be the answer, relative to the others.
+#[derive(Debug, Clone)]
+pub struct Prior {
+    weights: HashMap<String, f64>,
+    /// Weight of words not in `weights`.
+    default: f64,
+}
+
+impl Prior {
+    /// Reads a frequency file with one `word count` (or `word,count`) pair per
+    /// line; counts and probabilities both work. Empty lines and lines
+    /// starting with `#` are skipped.
+    pub fn load(path: &str, config: &PriorConfig) -> Result<Self> {
+        let content = error::read_file(path)?;
+
+        let mut frequencies: Vec<(String, f64)> = Vec::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split(|c: char| c == ',' || c.is_whitespace()).filter(|f| !f.is_empty());
+            let syntax = |message: String| Error::Syntax { path: path.to_string(), line: number + 1, message };
+            let (Some(word), Some(value), None) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(syntax(format!("expected '<word> <count>', got '{}'", line)));
+            };
+            let frequency: f64 = value
+                .parse()
+                .ok()
+                .filter(|f: &f64| f.is_finite() && *f >= 0.0)
+                .ok_or_else(|| syntax(format!("invalid count '{}'", value)))?;
+
+            frequencies.push((word.to_lowercase(), frequency));
+        }
+
+        Ok(Self::from_frequencies(frequencies, config))
+    }
+
+    pub fn from_frequencies(mut frequencies: Vec<(String, f64)>, config: &PriorConfig) -> Self {
+        // Most frequent first, for the rank based sigmoid
+        frequencies.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let max = frequencies.first().map_or(0.0, |(_, f)| *f);
+
+        let weights = frequencies
+            .into_iter()
+            .enumerate()
+            .map(|(rank, (word, frequency))| {
+                let weight = match config.smoothing {
+                    _ if max == 0.0 => 0.0,
+                    Smoothing::Linear => frequency / max,
+                    Smoothing::Log => (1.0 + frequency).ln() / (1.0 + max).ln(),
+                    Smoothing::Sigmoid => 1.0 / (1.0 + ((rank as f64 - config.center as f64) / config.width).exp()),
+                };
+                (word, weight.max(MIN_WEIGHT))
+            })
+            .collect();
+
+        Self { weights, default: MIN_WEIGHT }
+    }
+
+    /// Every word equally likely.
+    pub fn uniform() -> Self {
+        Self {
+            weights: HashMap::new(),
+            default: 1.0,
+        }
+    }
+
+    /// Makes `word` `factor` times as likely as before.
+    pub fn downweight(&mut self, word: &str, factor: f64) {
+        let weight = self.weight(word);
+        self.weights.insert(word.to_string(), (weight * factor).max(MIN_WEIGHT));
+    }
+
+    /// Relative likelihood of `word`, between `MIN_WEIGHT` and 1.
+    pub fn weight(&self, word: &str) -> f64 {
+        self.weights.get(word).copied().unwrap_or(self.default)
+    }
+
+    /// Probability of each of `words` being the answer, given it is one of them.
+    pub fn probabilities(&self, words: &[&str]) -> Vec<f64> {
+        let weights: Vec<f64> = words.iter().map(|w| self.weight(w)).collect();
+        let total: f64 = weights.iter().sum();
+        weights.into_iter().map(|w| w / total).collect()
+    }
+}
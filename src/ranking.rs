@@ -1,22 +1,67 @@
 use crate::analysis::LetterStats;
-use anyhow::Result;
-use serde_json;
+use crate::patterns::{pattern_code, SOLVED_CODE};
+use crate::error::{Error, Result};
+use rayon::prelude::*;
 use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Number of distinct feedback patterns (3^5).
+pub const PATTERN_COUNT: usize = 243;
+
+/// How the solver ranks its next guess.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RankMethod {
+    /// Letter frequency blend from `solver_config.json`, over the candidates only.
+    Weighted,
+    /// Smallest worst-case feedback bucket, over all allowed guesses.
+    Minimax,
+    /// Fewest expected candidates left after the feedback, over all allowed guesses.
+    Expected,
+    /// Top guesses of a base ranking, re-ranked by expected total guesses with
+    /// the best follow-up in every bucket (see `lookahead::LookaheadConfig`).
+    Lookahead,
+}
+
+impl FromStr for RankMethod {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "weighted" => Ok(Self::Weighted),
+            "minimax" => Ok(Self::Minimax),
+            "expected" => Ok(Self::Expected),
+            "lookahead" => Ok(Self::Lookahead),
+            _ => Err(Error::UnknownName { kind: "strategy", name: s.to_string(), expected: "weighted, minimax, expected or lookahead" }),
+        }
+    }
+}
 
-pub fn rank_words<'a>(words: &[&'a str], stats_json: &str) -> Result<Vec<(String, f64)>> {
-    let stats: LetterStats = serde_json::from_str(stats_json)?;
+impl RankMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Weighted => "weighted",
+            Self::Minimax => "minimax",
+            Self::Expected => "expected",
+            Self::Lookahead => "lookahead",
+        }
+    }
+}
 
-    // === Adjustable weight parameters ===
-    let w_pos = 0.2;      // weight for positional frequency
-    let w_overall = 0.1;  // weight for overall frequency
-    let w_unique = 0.7;   // weight for letter uniqueness
+/// `(w_pos, w_overall, w_unique)` used by `rank_words`.
+pub const DEFAULT_WEIGHTS: (f64, f64, f64) = (
+    0.2, // weight for positional frequency
+    0.1, // weight for overall frequency
+    0.7, // weight for letter uniqueness
+);
 
-    weighted_rank(words, stats_json, (w_pos, w_overall, w_unique))
+pub fn rank_words(words: &[&str], stats: &LetterStats) -> Vec<(String, f64)> {
+    weighted_rank_stats(words, stats, DEFAULT_WEIGHTS)
 }
 
-pub fn weighted_rank<'a>(words: &[&'a str], stats_json: &str, weights: (f64, f64, f64)) -> Result<Vec<(String, f64)>> {
+/// Ranks `words` by the blend of positional frequency, overall frequency and
+/// uniqueness given by `weights`, best first.
+pub fn weighted_rank_stats(words: &[&str], stats: &LetterStats, weights: (f64, f64, f64)) -> Vec<(String, f64)> {
     let (w_pos, w_overall, w_unique) = weights;
-        let stats: LetterStats = serde_json::from_str(stats_json)?;
 
     // === Compute positional totals ===
     let mut totals = [0.0; 5];
@@ -35,13 +80,11 @@ pub fn weighted_rank<'a>(words: &[&'a str], stats_json: &str, weights: (f64, f64
         grand_total += sum;
     }
 
-    // === Rank each word ===
-    let mut scores: Vec<(String, f64)> = Vec::new();
-
-    for &word in words {
+    // === Rank each word (in parallel, the collected order stays the input order) ===
+    let mut scores: Vec<(String, f64)> = words.par_iter().filter_map(|&word| {
         let chars: Vec<char> = word.chars().collect();
         if chars.len() != 5 || !chars.iter().all(|c| c.is_ascii_lowercase()) {
-            continue;
+            return None;
         }
 
         let mut score_pos = 0.0;
@@ -82,11 +125,129 @@ pub fn weighted_rank<'a>(words: &[&'a str], stats_json: &str, weights: (f64, f64
         let blended_score =
             w_pos * score_pos + w_overall * score_overall + w_unique * uniqueness;
 
-        scores.push((word.to_string(), blended_score));
-    }
+        Some((word.to_string(), blended_score))
+    }).collect();
 
-    // Sort descending by score
+    // Sort descending by score (stable, so ties keep the word list order)
     scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
 
-    Ok(scores)
-}
\ No newline at end of file
+    scores
+}
+
+/// How a guess splits the candidates into feedback buckets.
+#[derive(Debug, Clone, Copy)]
+pub struct BucketStats {
+    /// Number of candidates split.
+    pub candidates: u32,
+    /// Size of the largest bucket.
+    pub worst: u32,
+    /// Sum of squared bucket sizes; divided by the number of candidates this is
+    /// the expected number of candidates left after the guess.
+    pub sum_sq: u64,
+    /// Whether the guess is one of the candidates (it has a `ccccc` bucket).
+    pub is_candidate: bool,
+}
+
+impl BucketStats {
+    pub fn from_codes(codes: impl Iterator<Item = u8>) -> Self {
+        let mut sizes = [0u32; PATTERN_COUNT];
+        let mut candidates = 0;
+        let mut worst = 0;
+        let mut sum_sq = 0u64;
+
+        for code in codes {
+            let size = &mut sizes[code as usize];
+            // (n + 1)² - n² = 2n + 1, so the sum is kept up to date as we go
+            sum_sq += 2 * *size as u64 + 1;
+            *size += 1;
+            worst = worst.max(*size);
+            candidates += 1;
+        }
+
+        Self {
+            candidates,
+            worst,
+            sum_sq,
+            is_candidate: sizes[SOLVED_CODE as usize] > 0,
+        }
+    }
+
+    /// Ordering used by minimax: smaller worst case first, then smaller expected
+    /// bucket, then candidates before non-candidates.
+    pub fn minimax_key(&self) -> (u32, u64, bool) {
+        (self.worst, self.sum_sq, !self.is_candidate)
+    }
+
+    /// Ordering used by the expected-remaining strategy: smaller expected
+    /// bucket first, then candidates before non-candidates.
+    pub fn expected_key(&self) -> (u64, bool) {
+        (self.sum_sq, !self.is_candidate)
+    }
+
+    /// Expected number of candidates left after seeing the feedback.
+    pub fn expected_remaining(&self) -> f64 {
+        if self.candidates == 0 {
+            0.0
+        } else {
+            self.sum_sq as f64 / self.candidates as f64
+        }
+    }
+}
+
+/// Ranks every guess by `key` of its buckets (smaller is better), with the
+/// bucket stats of guess `g` supplied by `buckets`, and scores them with `score`.
+pub fn rank_by_buckets<K, B, F, S>(guesses: &[&str], buckets: B, key: F, score: S) -> Vec<(String, f64)>
+where
+    K: Ord,
+    B: Fn(usize) -> BucketStats + Sync,
+    F: Fn(&BucketStats) -> K,
+    S: Fn(&BucketStats) -> f64,
+{
+    let mut scored: Vec<(usize, BucketStats)> = (0..guesses.len())
+        .into_par_iter()
+        .map(|g| (g, buckets(g)))
+        .collect();
+
+    // Stable, so remaining ties keep the word list order
+    scored.sort_by_key(|(_, stats)| key(stats));
+
+    scored
+        .into_iter()
+        .map(|(g, stats)| (guesses[g].to_string(), score(&stats)))
+        .collect()
+}
+
+/// Index of the guess with the smallest `key`, with the bucket stats of guess
+/// `g` supplied by `buckets`, e.g. from a `PatternMatrix`. Ties go to the lower index.
+pub fn best_by<K, F, B>(guesses: usize, buckets: B, key: F) -> Option<usize>
+where
+    K: Ord + Send,
+    F: Fn(&BucketStats) -> K + Sync,
+    B: Fn(usize) -> BucketStats + Sync,
+{
+    (0..guesses)
+        .into_par_iter()
+        .map(|g| (key(&buckets(g)), g))
+        .min()
+        .map(|(_, g)| g)
+}
+
+/// Expected number of candidates left after a guess, given the pattern code and
+/// probability of every candidate (summing to 1), and whether the guess is a candidate.
+pub fn prior_remaining(codes: impl Iterator<Item = (u8, f64)>) -> (f64, bool) {
+    let mut sizes = [0u32; PATTERN_COUNT];
+    let mut mass = [0.0; PATTERN_COUNT];
+    for (code, probability) in codes {
+        sizes[code as usize] += 1;
+        mass[code as usize] += probability;
+    }
+
+    let remaining = sizes.iter().zip(&mass).map(|(&size, &mass)| size as f64 * mass).sum();
+    (remaining, sizes[SOLVED_CODE as usize] > 0)
+}
+
+/// Buckets of `guess` over the candidates, scoring patterns on the fly.
+pub fn candidate_buckets(guess: &str, candidates: &[&str]) -> BucketStats {
+    let guess = guess.as_bytes();
+    BucketStats::from_codes(candidates.iter().map(|c| pattern_code(guess, c.as_bytes())))
+}
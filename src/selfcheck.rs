@@ -0,0 +1,217 @@
+use crate::error::Result;
+use crate::filter::Filter;
+use crate::game::{GameData, Pattern};
+use crate::play::evaluate;
+use crate::wordlist;
+use crate::wordset::{Bitset, WordSet};
+use rand::prelude::IndexedRandom;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// The filter edge cases documented in docs/Solver.md, as the answer and the
+/// guesses played.
+pub const KNOWN_CASES: [(&str, &[&str]); 3] = [
+    ("salsa", &["salet", "salon", "salic", "salps", "salad"]),
+    ("lathe", &["salet", "table"]),
+    ("abhor", &["salet", "moray", "aroid"]),
+];
+
+/// A guess and the feedback it got.
+pub type Line = (String, Pattern);
+
+/// A word the filter gets wrong for a history, with the history reduced to
+/// the fewest lines that still show the problem.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Mismatch {
+    pub lines: Vec<Line>,
+    pub word: String,
+    /// Whether the filter keeps a word that gives other patterns; otherwise
+    /// it drops one that gives exactly these.
+    pub kept: bool,
+    /// The rule that drops the word, from `Filter::explain`.
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kept {
+            writeln!(f, "the filter keeps '{}', which doesn't give these patterns:", self.word)?;
+        } else {
+            writeln!(f, "the filter drops '{}', which gives these patterns:", self.word)?;
+        }
+        for (guess, pattern) in &self.lines {
+            let actual = evaluate(guess, &self.word).pattern();
+            if actual == *pattern {
+                writeln!(f, "  {} {}", guess, pattern)?;
+            } else {
+                writeln!(f, "  {} {}  ('{}' gives {})", guess, pattern, self.word, actual)?;
+            }
+        }
+        if let Some(reason) = &self.reason {
+            writeln!(f, "  dropped by {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Compares the candidates `Filter::filter_words` keeps with a brute-force
+/// oracle: every word whose `evaluate` patterns equal the entered ones.
+pub struct SelfCheck<'a> {
+    words: &'a [String],
+    set: WordSet,
+}
+
+impl<'a> SelfCheck<'a> {
+    pub fn new(words: &'a [String]) -> Result<Self> {
+        Ok(Self { words, set: WordSet::new(words)? })
+    }
+
+    /// Checks one history over every word.
+    pub fn check(&self, lines: &[Line]) -> Result<Vec<Mismatch>> {
+        let kept = self.filter(lines, &self.set.all())?;
+
+        let mut mismatches = BTreeSet::new();
+        for (index, word) in self.words.iter().enumerate() {
+            if consistent(lines, word) != kept.contains(index) {
+                mismatches.insert(self.minimize(lines, index)?);
+            }
+        }
+
+        Ok(mismatches.into_iter().collect())
+    }
+
+    /// Checks the histories of docs/Solver.md's edge cases.
+    pub fn check_known(&self) -> Result<Vec<Mismatch>> {
+        let histories: Vec<Vec<Line>> = KNOWN_CASES
+            .iter()
+            .map(|(answer, guesses)| history(answer, guesses.iter().copied()))
+            .collect();
+
+        self.check_all(&histories)
+    }
+
+    /// Checks `samples` random histories of 1 to `max_lines` random guesses
+    /// against a random answer.
+    pub fn check_random(&self, samples: usize, max_lines: usize, seed: u64) -> Result<Vec<Mismatch>> {
+        let answers = wordlist::answers(self.words);
+        let histories: Vec<Vec<Line>> = (0..samples)
+            .map(|sample| {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(sample as u64));
+                let answer = answers.choose(&mut rng).unwrap_or(&self.words[0]);
+                let count = rng.random_range(1..=max_lines.max(1));
+                let guesses: Vec<&str> = (0..count)
+                    .filter_map(|_| self.words.choose(&mut rng).map(String::as_str))
+                    .collect();
+                history(answer, guesses)
+            })
+            .collect();
+
+        self.check_all(&histories)
+    }
+
+    /// Checks every single guess against every answer. Scores every guess
+    /// against every word once, so it takes a while.
+    pub fn check_all_pairs(&self) -> Result<Vec<Mismatch>> {
+        let answers_start = self.words.len() - wordlist::answers(self.words).len();
+
+        let mismatches: Vec<Vec<Mismatch>> = self
+            .words
+            .par_iter()
+            .map(|guess| {
+                // The oracle: every word grouped by the pattern it gives for this guess
+                let codes: Vec<u8> = self.words.iter().map(|word| evaluate(guess, word).pattern().code()).collect();
+                let patterns: BTreeSet<u8> = codes[answers_start..].iter().copied().collect();
+
+                let mut mismatches = Vec::new();
+                for code in patterns {
+                    let line = (guess.clone(), Pattern::from_code(code));
+                    let kept = self.filter(std::slice::from_ref(&line), &self.set.all())?;
+                    for (index, &word_code) in codes.iter().enumerate() {
+                        let oracle = word_code == code;
+                        if oracle != kept.contains(index) {
+                            mismatches.push(self.mismatch(vec![line.clone()], index)?);
+                        }
+                    }
+                }
+                Ok(mismatches)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(mismatches.into_iter().flatten().collect())
+    }
+
+    fn check_all(&self, histories: &[Vec<Line>]) -> Result<Vec<Mismatch>> {
+        let mismatches: Vec<Vec<Mismatch>> = histories
+            .par_iter()
+            .map(|lines| self.check(lines))
+            .collect::<Result<_>>()?;
+
+        let unique: BTreeSet<Mismatch> = mismatches.into_iter().flatten().collect();
+        Ok(unique.into_iter().collect())
+    }
+
+    /// Drops lines from the history as long as the filter still gets the word wrong.
+    fn minimize(&self, lines: &[Line], index: usize) -> Result<Mismatch> {
+        let word = &self.words[index];
+        let mut single = Bitset::empty(self.set.len());
+        single.insert(index);
+
+        let mut lines = lines.to_vec();
+        let mut line = 0;
+        while line < lines.len() && lines.len() > 1 {
+            let mut reduced = lines.clone();
+            reduced.remove(line);
+            let kept = self.filter(&reduced, &single)?.contains(index);
+            if kept != consistent(&reduced, word) {
+                lines = reduced;
+            } else {
+                line += 1;
+            }
+        }
+
+        self.mismatch(lines, index)
+    }
+
+    fn mismatch(&self, lines: Vec<Line>, index: usize) -> Result<Mismatch> {
+        let word = &self.words[index];
+        let game = game(&lines)?;
+        let filter = Filter::new(&game, &self.set);
+        let mut single = Bitset::empty(self.set.len());
+        single.insert(index);
+
+        Ok(Mismatch {
+            kept: filter.filter_words(&single).contains(index),
+            reason: filter.explain(word)?.map(|elimination| elimination.to_string()),
+            lines,
+            word: word.clone(),
+        })
+    }
+
+    fn filter(&self, lines: &[Line], candidates: &Bitset) -> Result<Bitset> {
+        Ok(Filter::new(&game(lines)?, &self.set).filter_words(candidates))
+    }
+}
+
+fn game(lines: &[Line]) -> Result<GameData> {
+    let mut game = GameData::new();
+    for (guess, pattern) in lines {
+        game.add_line(guess, pattern)?;
+    }
+    Ok(game)
+}
+
+/// The lines of playing `guesses` against `answer`.
+fn history<'w>(answer: &str, guesses: impl IntoIterator<Item = &'w str>) -> Vec<Line> {
+    guesses
+        .into_iter()
+        .map(|guess| (guess.to_string(), evaluate(guess, answer).pattern()))
+        .collect()
+}
+
+/// The oracle: whether `word` as the answer gives every line's pattern.
+fn consistent(lines: &[Line], word: &str) -> bool {
+    lines.iter().all(|(guess, pattern)| evaluate(guess, word).pattern() == *pattern)
+}
@@ -1,53 +1,147 @@
 use std::io::{self, Write};
-use anyhow::Result;
-use std::fs;
-use anyhow::anyhow;
-use crate::ranking::weighted_rank;
+use crate::analysis::{LetterStats, STATS_PATH};
+use crate::error::{self, Error, Result};
+use crate::contradiction::{self, Contradiction};
 use crate::filter::Filter;
-use crate::game::GameData;
+use crate::game::{GameData, Pattern};
+use crate::endgame::{Endgame, EndgameConfig};
+use crate::patterns::pattern_code;
+use crate::prior::Prior;
+use crate::strategy::{Context, Ranking, Strategy};
+use std::sync::Arc;
+use crate::tree::TreeNode;
+use crate::ranking::DEFAULT_WEIGHTS;
+use crate::wordlist;
+use crate::wordset::{Bitset, WordSet};
+use serde::Serialize;
+
+/// What the solver suggests for the lines entered so far, as `suggest` shows it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestions {
+    /// The entered lines as `word:pattern`.
+    pub history: Vec<String>,
+    pub solved: bool,
+    /// Whether the exact endgame search ranked the guesses instead of the strategy.
+    pub endgame: bool,
+    /// The best guesses, at most `top_n`.
+    pub suggestions: Vec<Suggestion>,
+    /// Guesses the strategy didn't score (lookahead beyond its budget), with
+    /// their base strategy score, filling up to `top_n`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unranked: Vec<Suggestion>,
+    /// The most likely answers, if a prior is set.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub likely_answers: Vec<LikelyAnswer>,
+    pub words_left: usize,
+    /// Every word that can still be the answer.
+    pub candidates: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Suggestion {
+    pub word: String,
+    pub score: f64,
+}
+
+impl From<(String, f64)> for Suggestion {
+    fn from((word, score): (String, f64)) -> Self {
+        Self { word, score }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LikelyAnswer {
+    pub word: String,
+    pub probability: f64,
+}
 
 pub struct Solver {
     game: GameData,
-    current_words: Vec<String>,
-    all_words: Vec<String>,
+    kept: Vec<usize>,           // lines kept despite contradicting, not checked against later lines
+    current_words: Bitset,
+    excluded: Bitset,           // never candidates, e.g. used answers
+    all_words: WordSet,
+    tree: Option<TreeNode>,     // current node of a loaded decision tree, if still followed
+    strategy: Box<dyn Strategy>,
+    endgame: EndgameConfig,
+    prior: Option<Arc<Prior>>,
+    hard_mode: bool,
+    top_n: usize,
 }
 
 impl Solver {
-    pub fn new() -> Result<Self> {
-        let content = fs::read_to_string("wordlist.txt")
-            .map_err(|e| anyhow!("Failed to read wordlist.txt: {}", e))?;
-
-        let words: Vec<String> = content
-            .lines()
-            .map(|w| w.trim().to_lowercase())
-            .filter(|w| w.len() == 5)
-            .collect();
-
-        if words.is_empty() {
-            return Err(anyhow!("wordlist.txt is empty or invalid"));
-        }
+    pub fn new(strategy: Box<dyn Strategy>) -> Result<Self> {
+        let words = WordSet::new(&wordlist::load_words()?)?;
 
         Ok(Self {
             game: GameData::new(),
-            current_words: words.clone(),   // filtered, may shrink during filtering
+            kept: Vec::new(),
+            current_words: words.all(),     // indices into all_words, filtered from scratch every turn
+            excluded: Bitset::empty(words.len()),
             all_words: words,               // full list stays available for checking
+            tree: None,
+            strategy,
+            endgame: EndgameConfig::default(),
+            prior: None,
+            hard_mode: false,
+            top_n: 10,
         })
     }
 
+    /// Sets when the solver switches to the exact endgame search.
+    pub fn set_endgame(&mut self, config: EndgameConfig) {
+        self.endgame = config;
+    }
+
+    /// Only suggests words that can still be the answer. The exact endgame
+    /// search may guess any word, so it is skipped in hard mode.
+    pub fn set_hard_mode(&mut self, hard_mode: bool) {
+        self.hard_mode = hard_mode;
+    }
+
+    /// Sets how many suggestions are shown every turn.
+    pub fn set_top_n(&mut self, top_n: usize) {
+        self.top_n = top_n;
+    }
+
+    /// Sets how likely each word is to be the answer, to show the most likely ones.
+    pub fn set_prior(&mut self, prior: Arc<Prior>) {
+        self.prior = Some(prior);
+    }
+
+    /// Drops these words from the candidates, e.g. answers that were already used.
+    pub fn exclude<'w>(&mut self, words: impl Iterator<Item = &'w str>) {
+        for index in words.filter_map(|word| self.all_words.index_of(word)) {
+            self.excluded.insert(index);
+        }
+        self.current_words.difference_with(&self.excluded);
+    }
+
+    /// Loads a decision tree (as written by `wordle-bot tree`) to take suggestions from.
+    pub fn load_tree(&mut self, path: &str) -> Result<()> {
+        self.tree = Some(error::parse_json(&error::read_file(path)?, path)?);
+        Ok(())
+    }
+
     pub fn run(&mut self) -> Result<()> {
         use crate::ranking::rank_words;
-        let stats_json = fs::read_to_string("letter_stats.json")?;
-        let word_refs: Vec<&str> = self.current_words.iter().map(|s| s.as_str()).collect();
-        let start_results = rank_words(&word_refs, &stats_json)?;
 
-        println!("Top 10 words by letter position frequency:");
-        for (word, score) in start_results.iter().take(10) {
-            println!("{word:<10} {score:.5}");
+        if let Some(tree) = &self.tree {
+            println!("Decision tree suggests: {}", tree.guess);
+        } else {
+            let stats = LetterStats::load(STATS_PATH)?;
+            let word_refs = self.all_words.strs(&self.current_words);
+            let start_results = rank_words(&word_refs, &stats);
+
+            println!("Top {} words by letter position frequency:", self.top_n);
+            for (word, score) in start_results.iter().take(self.top_n) {
+                println!("{word:<10} {score:.5}");
+            }
         }
 
         loop {
             // Step 1: enter word
-            print!("Enter your 5-letter guess (or 'exit'): ");
+            print!("Enter your 5-letter guess (or 'why <word>', 'exit'): ");
             io::stdout().flush()?;
             let mut word = String::new();
             io::stdin().read_line(&mut word)?;
@@ -57,13 +151,17 @@ impl Solver {
                 println!("Exiting solver.");
                 break;
             }
+            if let Some(target) = word.strip_prefix("why ") {
+                self.why(target.trim());
+                continue;
+            }
             if word.len() != 5 {
                 println!("Please enter a 5-letter word.\n");
                 continue;
             }
 
             // Check if guess exists in wordlist (uses preloaded all_words)
-            if !self.all_words.contains(&word) {
+            if self.all_words.index_of(&word).is_none() {
                 println!("'{}' is not in the wordlist.\n", word);
                 continue;
             }
@@ -73,15 +171,20 @@ impl Solver {
             io::stdout().flush()?;
             let mut pattern = String::new();
             io::stdin().read_line(&mut pattern)?;
-            let pattern = pattern.trim().to_lowercase();
-
-            if pattern.len() != 5 || !pattern.chars().all(|c| "wmc".contains(c)) {
+            let Ok(pattern) = pattern.trim().to_lowercase().parse::<Pattern>() else {
                 println!("Invalid pattern. Use only w, m, c.\n");
                 continue;
-            }
+            };
 
-            // Update game
-            self.game.add_line(&word, &pattern);
+            // Update game, unless the line contradicts the earlier ones and is undone
+            self.game.add_line(&word, &pattern)?;
+            let pattern = match contradiction::find(&self.game.lines, &self.kept, &self.all_words) {
+                Some(contradiction) => match self.resolve(&contradiction)? {
+                    Some(pattern) => pattern,
+                    None => continue,
+                },
+                None => pattern,
+            };
 
             // Show summary
             self.game.print_summary();
@@ -93,46 +196,229 @@ impl Solver {
                 break;
             }
 
-            // Update suggestions
-            let stats_json = fs::read_to_string("letter_stats.json")
-                .map_err(|e| anyhow!("Failed to read letter_stats.json: {}", e))?;
-            self.rank_words(&stats_json)?;
+            // Update suggestions, from the decision tree as long as it is followed
+            if self.follow_tree(&word, &pattern) {
+                continue;
+            }
+
+            self.suggest();
         }
 
         Ok(())
     }
 
-    pub fn rank_words(&mut self, stats_json: &str) -> Result<()> {
-        // Read solver_config.json as Vec of tuples
-        let config_content = fs::read_to_string("solver_config.json")
-            .map_err(|e| anyhow!("Failed to read solver_config.json: {}", e))?;
+    /// Reports the contradicting new line and lets the user apply a fix, undo
+    /// the line or keep it. Returns the line's pattern afterwards, or `None`
+    /// once the line is undone. A kept line is left out of later checks.
+    fn resolve(&mut self, contradiction: &Contradiction) -> Result<Option<Pattern>> {
+        let line = &self.game.lines[contradiction.line];
+        let (word, pattern) = (line.word.clone(), line.pattern());
 
-        let weights: Vec<(f64, f64, f64)> = serde_json::from_str(&config_content)
-            .map_err(|e| anyhow!("Failed to parse solver_config.json: {}", e))?;
+        println!("\nNo word in the word list matches every line entered so far.");
+        println!("{}", contradiction);
+        for (number, fix) in contradiction.fixes.iter().enumerate() {
+            let letter = word.as_bytes()[fix.cell] as char;
+            println!(
+                "  {}. {} {}: cell {} ('{}') {} ({} words left)",
+                number + 1, word, fix.pattern, fix.cell + 1, letter, fix.state.name(), fix.matches
+            );
+        }
+        println!("  u. undo {} {}", word, pattern);
+        println!("  k. keep it anyway");
 
-        // Select weight set based on number of guesses
-        let attempt = self.game.lines.len().min(weights.len() - 1);
-        let weight_tuple = weights[attempt];
+        loop {
+            print!("Choose a fix, 'u' or 'k': ");
+            io::stdout().flush()?;
+            let mut choice = String::new();
+            if io::stdin().read_line(&mut choice)? == 0 {
+                choice = "k".to_string();
+            }
+
+            match choice.trim() {
+                "u" => {
+                    self.game.remove_line(contradiction.line)?;
+                    println!("Removed line {}.\n", contradiction.line + 1);
+                    return Ok(None);
+                }
+                "k" => {
+                    self.kept.push(contradiction.line);
+                    return Ok(Some(pattern));
+                }
+                choice => {
+                    let fix = choice
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|number| contradiction.fixes.get(number.wrapping_sub(1)));
+                    if let Some(fix) = fix {
+                        self.game.set_pattern(contradiction.line, &fix.pattern)?;
+                        println!("Line {} is now {} {}.", contradiction.line + 1, word, fix.pattern);
+                        return Ok(Some(fix.pattern));
+                    }
+                    println!("Invalid choice.");
+                }
+            }
+        }
+    }
 
-        // Update wordlist (filtered)
+    /// Prints which line and rule rule out `word`, or that it is still a candidate.
+    pub fn why(&self, word: &str) {
+        let Some(index) = self.all_words.index_of(word) else {
+            println!("'{}' is not in the wordlist.\n", word);
+            return;
+        };
+
+        let filter = Filter::new(&self.game, &self.all_words);
+        let elimination = match filter.explain(word) {
+            Ok(elimination) => elimination,
+            Err(e) => {
+                println!("{}\n", e);
+                return;
+            }
+        };
+
+        if let Some(elimination) = elimination {
+            println!("'{}' is eliminated by {}\n", word, elimination);
+        } else if let Some((line, pattern)) = filter.mismatch(self.all_words.word(index)) {
+            let line_data = &self.game.lines[line];
+            println!(
+                "'{}' passes the filter's rules, but line {} ({} {}) would be {} for it.\n",
+                word, line + 1, line_data.word, line_data.pattern(), pattern
+            );
+        } else if self.excluded.contains(index) {
+            println!("'{}' is consistent with every line, but was excluded (e.g. as a used answer).\n", word);
+        } else {
+            println!("'{}' is consistent with every line and still a candidate.\n", word);
+        }
+    }
+
+    /// Enters a line without asking, and returns the contradiction if no word
+    /// matches it together with the earlier lines. The line is kept either way.
+    pub fn add_line(&mut self, word: &str, pattern: &Pattern) -> Result<Option<Contradiction>> {
+        if self.all_words.index_of(word).is_none() {
+            return Err(Error::UnknownWord(word.to_string()));
+        }
+
+        self.game.add_line(word, pattern)?;
+        let contradiction = contradiction::find(&self.game.lines, &self.kept, &self.all_words);
+        if let Some(contradiction) = &contradiction {
+            self.kept.push(contradiction.line);
+        }
+        Ok(contradiction)
+    }
+
+    /// Filters the candidates by the lines so far and ranks the next guesses,
+    /// without printing anything.
+    pub fn suggestions(&mut self) -> Suggestions {
         self.current_words = self.update_wordlist();
 
-        // Prepare for ranking
-        let word_refs: Vec<&str> = self.current_words.iter().map(|s| s.as_str()).collect();
-        let ranked_words = weighted_rank(&word_refs, stats_json, weight_tuple)?;
+        let solved = self.is_game_won();
+        let left = self.current_words.count();
+        let ranking = if solved {
+            Ranking::default()
+        } else {
+            rank_guesses(
+                self.strategy.as_ref(),
+                &self.endgame,
+                self.hard_mode,
+                &self.all_words,
+                &self.current_words,
+                self.game.lines.len(),
+            )
+        };
+
+        Suggestions {
+            history: self.game.lines.iter().map(|line| format!("{}:{}", line.word, line.pattern())).collect(),
+            solved,
+            endgame: !solved && !self.hard_mode && self.endgame.applies(left),
+            suggestions: ranking.ranked.iter().take(self.top_n).cloned().map(Suggestion::from).collect(),
+            unranked: ranking
+                .unranked
+                .into_iter()
+                .take(self.top_n.saturating_sub(ranking.ranked.len()))
+                .map(Suggestion::from)
+                .collect(),
+            likely_answers: self.likely_answers(),
+            words_left: left,
+            candidates: self.all_words.strs(&self.current_words).into_iter().map(str::to_string).collect(),
+        }
+    }
+
+    pub fn suggest(&mut self) {
+        let suggestions = self.suggestions();
+        self.print_suggestions(&suggestions);
+    }
 
-        println!("Top suggested words:");
-        for (word, score) in ranked_words.iter().take(10) {
+    /// Prints suggestions the way the interactive solver shows them every turn.
+    pub fn print_suggestions(&self, suggestions: &Suggestions) {
+        if suggestions.endgame {
+            println!("Exact endgame search over {} words ({}):", suggestions.words_left, self.endgame.objective.label());
+        } else {
+            println!("Top suggested words:");
+        }
+        for Suggestion { word, score } in &suggestions.suggestions {
             println!("{word:<10} {score:.5}");
         }
-        println!("Total Words Left: {}\n", self.current_words.len());
+        if !suggestions.unranked.is_empty() {
+            println!("Not looked ahead (base strategy score):");
+            for Suggestion { word, score } in &suggestions.unranked {
+                println!("{word:<10} {score:.5}");
+            }
+        }
+        print_likely(&suggestions.likely_answers);
+        println!("Total Words Left: {}\n", suggestions.words_left);
+    }
 
-        Ok(())
+    /// The five most likely remaining answers, or none without a prior.
+    fn likely_answers(&self) -> Vec<LikelyAnswer> {
+        let Some(prior) = &self.prior else {
+            return Vec::new();
+        };
+
+        let word_refs = self.all_words.strs(&self.current_words);
+        let mut likely: Vec<(&str, f64)> = word_refs.iter().copied().zip(prior.probabilities(&word_refs)).collect();
+        likely.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        likely
+            .into_iter()
+            .take(5)
+            .map(|(word, probability)| LikelyAnswer { word: word.to_string(), probability })
+            .collect()
+    }
+
+    /// Moves the decision tree along the entered line and prints its next
+    /// suggestion. Returns `false` once the user has left the tree.
+    fn follow_tree(&mut self, word: &str, pattern: &Pattern) -> bool {
+        let Some(mut node) = self.tree.take() else {
+            return false;
+        };
+
+        if node.guess != word {
+            println!("'{}' deviates from the decision tree ('{}'), falling back to live ranking.", word, node.guess);
+            return false;
+        }
+
+        let Some(child) = node.children.remove(pattern) else {
+            println!("Pattern '{}' is not covered by the decision tree, falling back to live ranking.", pattern);
+            return false;
+        };
+
+        self.current_words = self.update_wordlist();
+        println!("Decision tree suggests: {}", child.guess);
+        print_likely(&self.likely_answers());
+        println!("Total Words Left: {}\n", self.current_words.count());
+
+        self.tree = Some(child);
+        true
     }
 
-    pub fn update_wordlist(&self) -> Vec<String> {
-        let filter = Filter::new(&self.game, &self.current_words);
-        filter.filter_words()
+    /// Filters every word but the excluded ones by the lines so far, so words
+    /// come back once a line is fixed or undone.
+    pub fn update_wordlist(&self) -> Bitset {
+        let mut words = self.all_words.all();
+        words.difference_with(&self.excluded);
+
+        let filter = Filter::new(&self.game, &self.all_words);
+        filter.consistent_words(&words)
     }
 
     fn is_game_won(&self) -> bool {
@@ -153,3 +439,93 @@ impl Solver {
         }
     }
 }
+
+/// Lists the most likely remaining answers, if there are any.
+fn print_likely(likely: &[LikelyAnswer]) {
+    if likely.is_empty() {
+        return;
+    }
+
+    println!("Most likely answers:");
+    for LikelyAnswer { word, probability } in likely {
+        println!("{word:<10} {:.1}%", probability * 100.0);
+    }
+}
+
+/// Ranks the next guesses the way the solver suggests them: by the exact
+/// endgame search once few candidates are left, and by the strategy otherwise.
+/// In hard mode only the candidates are ranked, and never by the endgame.
+pub fn rank_guesses(
+    strategy: &dyn Strategy,
+    endgame: &EndgameConfig,
+    hard_mode: bool,
+    words: &WordSet,
+    candidates: &Bitset,
+    turn: usize,
+) -> Ranking {
+    // Few words left: search exhaustively instead of using the strategy, with
+    // every candidate equally likely whatever the strategy's prior
+    if !hard_mode && endgame.applies(candidates.count()) {
+        let candidates: Vec<usize> = candidates.iter().collect();
+        let mut search = Endgame::new(words.len(), endgame.objective, |g, c| {
+            pattern_code(words.word(g), words.word(c))
+        });
+
+        let ranked = search
+            .rank(&candidates)
+            .into_iter()
+            .map(|(g, score)| (words.as_str(g).to_string(), score))
+            .collect();
+        return Ranking { ranked, unranked: Vec::new() };
+    }
+
+    let word_refs = words.strs(candidates);
+    let all_refs = if hard_mode {
+        word_refs.clone()
+    } else {
+        words.strs(&words.all())
+    };
+    strategy.rank_grouped(&Context::new(&all_refs, &word_refs, turn))
+}
+
+/// Picks the weights for the given number of guesses already made, reusing the
+/// last entry once the game runs longer than the config. Without any weights
+/// the blend of `ranking::rank_words` is used.
+pub fn weights_for_turn(weights: &[(f64, f64, f64)], guesses: usize) -> (f64, f64, f64) {
+    weights
+        .get(guesses)
+        .or(weights.last())
+        .copied()
+        .unwrap_or(DEFAULT_WEIGHTS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookahead::LookaheadConfig;
+    use crate::ranking::RankMethod;
+    use crate::strategy::{self, Options};
+
+    fn solver() -> Solver {
+        let options = Options {
+            stats: LetterStats::load(STATS_PATH).unwrap(),
+            weights: Vec::new(),
+            live_stats: false,
+            lookahead: LookaheadConfig::default(),
+            prior: None,
+        };
+        Solver::new(strategy::build(RankMethod::Weighted, &options).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn wrong_guess_is_not_suggested_again() {
+        // The filter's rules keep 'allay' here, and the endgame search took it for the answer
+        let mut solver = solver();
+        solver.add_line("salet", &"wmcww".parse().unwrap()).unwrap();
+        solver.add_line("allay", &"cccwc".parse().unwrap()).unwrap();
+
+        let suggestions = solver.suggestions();
+        assert_eq!(suggestions.candidates, ["alloy"]);
+        assert_eq!(suggestions.suggestions[0].word, "alloy");
+    }
+}
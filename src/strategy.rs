@@ -0,0 +1,261 @@
+use crate::analysis::LetterStats;
+use crate::lookahead::{lookahead_rank, LookaheadConfig};
+use crate::patterns::{pattern_code, PatternMatrix};
+use crate::prior::Prior;
+use crate::ranking::{
+    best_by, candidate_buckets, prior_remaining, rank_by_buckets, weighted_rank_stats, BucketStats, RankMethod,
+};
+use crate::solver::weights_for_turn;
+use crate::error::{Error, Result};
+use rayon::prelude::*;
+use std::sync::Arc;
+
+/// Everything a strategy gets to pick the next guess from.
+pub struct Context<'a> {
+    /// Every allowed guess, in word list order.
+    pub guesses: &'a [&'a str],
+    /// The words that are still possible.
+    pub candidates: &'a [&'a str],
+    /// Number of guesses already made.
+    pub turn: usize,
+    /// Precomputed patterns, with the candidates' indices into the matrix's answers.
+    matrix: Option<(&'a PatternMatrix, &'a [usize])>,
+}
+
+impl<'a> Context<'a> {
+    pub fn new(guesses: &'a [&'a str], candidates: &'a [&'a str], turn: usize) -> Self {
+        Self {
+            guesses,
+            candidates,
+            turn,
+            matrix: None,
+        }
+    }
+
+    /// Looks patterns up in `matrix` instead of scoring them on the fly.
+    /// `guesses` must be the matrix's guesses, and `answers` the indices of
+    /// the candidates among its answers.
+    pub fn with_matrix(mut self, matrix: &'a PatternMatrix, answers: &'a [usize]) -> Self {
+        self.matrix = Some((matrix, answers));
+        self
+    }
+
+    /// Pattern of guess `g` against candidate `c`.
+    pub fn code(&self, g: usize, c: usize) -> u8 {
+        match self.matrix {
+            Some((matrix, answers)) => matrix.row(g)[answers[c]],
+            None => pattern_code(self.guesses[g].as_bytes(), self.candidates[c].as_bytes()),
+        }
+    }
+
+    /// How guess `g` splits the candidates.
+    pub fn buckets(&self, g: usize) -> BucketStats {
+        match self.matrix {
+            Some((matrix, answers)) => {
+                let row = matrix.row(g);
+                BucketStats::from_codes(answers.iter().map(|&a| row[a]))
+            }
+            None => candidate_buckets(self.guesses[g], self.candidates),
+        }
+    }
+
+    /// Expected number of candidates left after guess `g` when the candidates
+    /// are the answer with the given probabilities, and whether `g` is a candidate.
+    pub fn prior_remaining(&self, g: usize, probabilities: &[f64]) -> (f64, bool) {
+        match self.matrix {
+            Some((matrix, answers)) => {
+                let row = matrix.row(g);
+                prior_remaining(answers.iter().map(|&a| row[a]).zip(probabilities.iter().copied()))
+            }
+            None => {
+                let guess = self.guesses[g].as_bytes();
+                let codes = self.candidates.iter().map(|c| pattern_code(guess, c.as_bytes()));
+                prior_remaining(codes.zip(probabilities.iter().copied()))
+            }
+        }
+    }
+}
+
+/// Guesses ranked by a strategy, and the guesses it couldn't score in the
+/// same units, which are listed separately.
+#[derive(Debug, Clone, Default)]
+pub struct Ranking {
+    pub ranked: Vec<(String, f64)>,
+    /// Guesses left unscored, e.g. beyond the lookahead budget, with the score
+    /// of the strategy they come from.
+    pub unranked: Vec<(String, f64)>,
+}
+
+/// A way of choosing the next guess, used by the solver, tree building and
+/// opener simulations alike.
+pub trait Strategy: Send + Sync {
+    /// Ranks guesses for the position, best first, with a strategy specific score.
+    fn rank(&self, ctx: &Context) -> Vec<(String, f64)>;
+
+    /// The guess to play. Strategies that can find it without ranking
+    /// every guess override this.
+    fn best(&self, ctx: &Context) -> Option<String> {
+        self.rank(ctx).into_iter().next().map(|(word, _)| word)
+    }
+
+    /// Like `rank`, but with the guesses the strategy couldn't score listed
+    /// separately. Only lookahead leaves guesses unscored.
+    fn rank_grouped(&self, ctx: &Context) -> Ranking {
+        Ranking { ranked: self.rank(ctx), unranked: Vec::new() }
+    }
+}
+
+/// Everything the strategies can be configured with.
+#[derive(Clone)]
+pub struct Options {
+    pub stats: LetterStats,
+    /// Weights of the weighted blend, one tuple per turn.
+    pub weights: Vec<(f64, f64, f64)>,
+    /// Whether the weighted blend recomputes the letter statistics from the candidates.
+    pub live_stats: bool,
+    pub lookahead: LookaheadConfig,
+    /// How likely each word is to be the answer, if known.
+    pub prior: Option<Arc<Prior>>,
+}
+
+/// Builds the strategy selected by `method`.
+pub fn build(method: RankMethod, options: &Options) -> Result<Box<dyn Strategy>> {
+    Ok(match method {
+        RankMethod::Weighted => Box::new(Weighted {
+            stats: options.stats.clone(),
+            weights: options.weights.clone(),
+            live_stats: options.live_stats,
+            prior: options.prior.clone(),
+        }),
+        RankMethod::Minimax => Box::new(Minimax),
+        RankMethod::Expected => Box::new(Expected {
+            prior: options.prior.clone(),
+        }),
+        RankMethod::Lookahead => {
+            if options.lookahead.base == RankMethod::Lookahead {
+                return Err(Error::Invalid("The lookahead base strategy can't be lookahead itself".to_string()));
+            }
+            Box::new(Lookahead {
+                base: build(options.lookahead.base, options)?,
+                config: options.lookahead,
+                prior: options.prior.clone(),
+            })
+        }
+    })
+}
+
+/// Letter frequency blend over the candidates only, with the weights for the
+/// current turn from `solver_config.json`. With a prior, every score is
+/// scaled by the word's likelihood.
+pub struct Weighted {
+    stats: LetterStats,
+    weights: Vec<(f64, f64, f64)>,
+    /// Recompute the letter statistics from the candidates every turn instead
+    /// of using the ones of the whole word list.
+    live_stats: bool,
+    prior: Option<Arc<Prior>>,
+}
+
+impl Strategy for Weighted {
+    fn rank(&self, ctx: &Context) -> Vec<(String, f64)> {
+        let weights = weights_for_turn(&self.weights, ctx.turn);
+        let mut ranked = if self.live_stats {
+            weighted_rank_stats(ctx.candidates, &LetterStats::from_candidates(ctx.candidates), weights)
+        } else {
+            weighted_rank_stats(ctx.candidates, &self.stats, weights)
+        };
+
+        if let Some(prior) = &self.prior {
+            for (word, score) in &mut ranked {
+                *score *= prior.weight(word);
+            }
+            // Stable, so ties keep the blend's order
+            ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        }
+        ranked
+    }
+}
+
+/// Ranks every guess by the size of its largest feedback bucket over the
+/// candidates (smaller is better). Ties are broken by the expected bucket size
+/// and then in favour of guesses that are candidates themselves.
+pub struct Minimax;
+
+impl Strategy for Minimax {
+    fn rank(&self, ctx: &Context) -> Vec<(String, f64)> {
+        rank_by_buckets(ctx.guesses, |g| ctx.buckets(g), BucketStats::minimax_key, |stats| stats.worst as f64)
+    }
+
+    fn best(&self, ctx: &Context) -> Option<String> {
+        best_by(ctx.guesses.len(), |g| ctx.buckets(g), BucketStats::minimax_key).map(|g| ctx.guesses[g].to_string())
+    }
+}
+
+/// Ranks every guess by the expected number of candidates left after its
+/// feedback (sum of squared bucket sizes over the total, smaller is better),
+/// preferring guesses that are candidates themselves on ties. With a prior,
+/// every candidate counts with its probability of being the answer.
+pub struct Expected {
+    prior: Option<Arc<Prior>>,
+}
+
+impl Expected {
+    /// Every guess with its expected remaining candidates under the prior and
+    /// whether it is a candidate, in guess order.
+    fn prior_scores(&self, ctx: &Context, prior: &Prior) -> Vec<(f64, bool)> {
+        let probabilities = prior.probabilities(ctx.candidates);
+        (0..ctx.guesses.len())
+            .into_par_iter()
+            .map(|g| ctx.prior_remaining(g, &probabilities))
+            .collect()
+    }
+}
+
+impl Strategy for Expected {
+    fn rank(&self, ctx: &Context) -> Vec<(String, f64)> {
+        let Some(prior) = &self.prior else {
+            return rank_by_buckets(ctx.guesses, |g| ctx.buckets(g), BucketStats::expected_key, BucketStats::expected_remaining);
+        };
+
+        let mut scored: Vec<(usize, (f64, bool))> = self.prior_scores(ctx, prior).into_iter().enumerate().collect();
+        // Stable, so remaining ties keep the word list order
+        scored.sort_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)));
+        scored
+            .into_iter()
+            .map(|(g, (remaining, _))| (ctx.guesses[g].to_string(), remaining))
+            .collect()
+    }
+
+    fn best(&self, ctx: &Context) -> Option<String> {
+        let Some(prior) = &self.prior else {
+            return best_by(ctx.guesses.len(), |g| ctx.buckets(g), BucketStats::expected_key)
+                .map(|g| ctx.guesses[g].to_string());
+        };
+
+        self.prior_scores(ctx, prior)
+            .into_iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)))
+            .map(|(g, _)| ctx.guesses[g].to_string())
+    }
+}
+
+/// Top guesses of a base strategy, re-ranked by expected total guesses with
+/// the best follow-up in every bucket (see `lookahead::LookaheadConfig`).
+/// With a prior, every candidate counts with its probability.
+pub struct Lookahead {
+    base: Box<dyn Strategy>,
+    config: LookaheadConfig,
+    prior: Option<Arc<Prior>>,
+}
+
+impl Strategy for Lookahead {
+    fn rank(&self, ctx: &Context) -> Vec<(String, f64)> {
+        self.rank_grouped(ctx).ranked
+    }
+
+    fn rank_grouped(&self, ctx: &Context) -> Ranking {
+        let base = self.base.rank(ctx);
+        lookahead_rank(ctx, &base, &self.config, self.prior.as_deref())
+    }
+}
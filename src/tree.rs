@@ -0,0 +1,219 @@
+use crate::patterns::{decode, PatternMatrix, SOLVED_CODE};
+use crate::game::Pattern;
+use crate::endgame::{Endgame, EndgameConfig};
+use crate::strategy::{Context, Strategy};
+use crate::error::{Error, Result};
+use rayon::prelude::*;
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+
+/// A node of the solver's decision tree: the guess to play, and for every
+/// feedback pattern it can produce the subtree to continue with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TreeNode {
+    pub guess: String,
+    /// Whether the guess itself is one of the remaining answers, i.e. whether
+    /// `ccccc` is a possible outcome of this node.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_answer: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub children: BTreeMap<Pattern, TreeNode>,
+}
+
+/// Summary of how the tree performs over all answers it covers.
+#[derive(Debug, Clone, Default)]
+pub struct TreeStats {
+    pub answers: usize,
+    pub total_guesses: usize,
+    pub max_guesses: usize,
+    /// Answers needing more than six guesses.
+    pub failures: usize,
+}
+
+impl TreeStats {
+    pub fn average(&self) -> f64 {
+        if self.answers == 0 {
+            0.0
+        } else {
+            self.total_guesses as f64 / self.answers as f64
+        }
+    }
+}
+
+impl TreeNode {
+    /// Returns every answer's path through the tree as `(guess, pattern)` pairs,
+    /// ending with the answer and `ccccc`.
+    pub fn paths(&self) -> Vec<Vec<(String, Pattern)>> {
+        let mut paths = Vec::new();
+        self.collect_paths(&mut Vec::new(), &mut paths);
+        paths
+    }
+
+    fn collect_paths(&self, prefix: &mut Vec<(String, Pattern)>, paths: &mut Vec<Vec<(String, Pattern)>>) {
+        if self.is_answer {
+            let mut path = prefix.clone();
+            path.push((self.guess.clone(), Pattern::SOLVED));
+            paths.push(path);
+        }
+
+        for (pattern, child) in &self.children {
+            prefix.push((self.guess.clone(), *pattern));
+            child.collect_paths(prefix, paths);
+            prefix.pop();
+        }
+    }
+
+    /// Number of guesses the tree needs for every answer.
+    pub fn guesses_per_answer(&self) -> BTreeMap<String, usize> {
+        self.paths()
+            .into_iter()
+            .filter_map(|path| path.last().map(|(answer, _)| (answer.clone(), path.len())))
+            .collect()
+    }
+
+    /// Compact text export: one line per answer, e.g. `salet:wcmmm table:mcwmc lathe:ccccc`.
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for path in self.paths() {
+            let line: Vec<String> = path
+                .iter()
+                .map(|(guess, pattern)| format!("{}:{}", guess, pattern))
+                .collect();
+            text.push_str(&line.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+
+    pub fn stats(&self) -> TreeStats {
+        let mut stats = TreeStats::default();
+        self.collect_stats(1, &mut stats);
+        stats
+    }
+
+    fn collect_stats(&self, depth: usize, stats: &mut TreeStats) {
+        if self.is_answer {
+            stats.answers += 1;
+            stats.total_guesses += depth;
+            stats.max_guesses = stats.max_guesses.max(depth);
+            if depth > 6 {
+                stats.failures += 1;
+            }
+        }
+
+        for child in self.children.values() {
+            child.collect_stats(depth + 1, stats);
+        }
+    }
+}
+
+/// Builds the decision tree the solver follows, by picking the top suggestion
+/// of the strategy for every group of answers a pattern leaves.
+pub struct TreeBuilder<'a> {
+    matrix: &'a PatternMatrix,
+    guesses: Vec<&'a str>,
+    strategy: &'a dyn Strategy,
+    endgame: EndgameConfig,
+    hard_mode: bool,
+    /// Indices into the matrix's answers the tree is built over.
+    answers: Vec<usize>,
+}
+
+impl<'a> TreeBuilder<'a> {
+    pub fn new(matrix: &'a PatternMatrix, strategy: &'a dyn Strategy) -> Self {
+        Self {
+            matrix,
+            guesses: matrix.guesses().iter().map(|s| s.as_str()).collect(),
+            strategy,
+            endgame: EndgameConfig::default(),
+            hard_mode: false,
+            answers: (0..matrix.answers().len()).collect(),
+        }
+    }
+
+    /// Leaves these words out of the answers the tree is built over.
+    pub fn exclude_answers(&mut self, words: &[&str]) {
+        let answers = self.matrix.answers();
+        self.answers.retain(|&a| !words.contains(&answers[a].as_str()));
+    }
+
+    /// Sets when the builder switches to the exact endgame search, like the solver.
+    pub fn set_endgame(&mut self, config: EndgameConfig) {
+        self.endgame = config;
+    }
+
+    /// Only guesses words that can still be the answer, like the solver in hard mode.
+    pub fn set_hard_mode(&mut self, hard_mode: bool) {
+        self.hard_mode = hard_mode;
+    }
+
+    /// Builds the tree over the matrix's answers (except excluded ones).
+    pub fn build(&self, opener: &str) -> Result<TreeNode> {
+        let guess = self
+            .matrix
+            .guess_index(opener)
+            .ok_or_else(|| Error::UnknownWord(opener.to_string()))?;
+        Ok(self.build_node(guess, &self.answers, 0))
+    }
+
+    /// The opener the strategy picks itself for the answers.
+    pub fn opener(&self) -> &str {
+        &self.matrix.guesses()[self.next_guess(&self.answers, 0)]
+    }
+
+    /// Index of the guess the solver would suggest for these answers after `guesses` guesses.
+    pub fn next_guess(&self, candidates: &[usize], guesses: usize) -> usize {
+        if !self.hard_mode && self.endgame.applies(candidates.len()) {
+            let mut endgame = Endgame::new(self.matrix.guesses().len(), self.endgame.objective, |g, a| {
+                self.matrix.row(g)[a]
+            });
+            if let Some((best, _)) = endgame.best(candidates) {
+                return best;
+            }
+        }
+
+        let words: Vec<&str> = candidates.iter().map(|&i| self.matrix.answers()[i].as_str()).collect();
+        let ctx = if self.hard_mode {
+            Context::new(&words, &words, guesses)
+        } else {
+            Context::new(&self.guesses, &words, guesses).with_matrix(self.matrix, candidates)
+        };
+
+        let best = self.strategy.best(&ctx);
+        let best = best.as_deref().unwrap_or(words[0]);
+        self.matrix.guess_index(best).expect("answers are allowed guesses")
+    }
+
+    fn build_node(&self, guess: usize, candidates: &[usize], guesses: usize) -> TreeNode {
+        let row = self.matrix.row(guess);
+
+        let mut is_answer = false;
+        let mut buckets: BTreeMap<u8, Vec<usize>> = BTreeMap::new();
+
+        for &answer in candidates {
+            let code = row[answer];
+            if code == SOLVED_CODE {
+                is_answer = true;
+            } else {
+                buckets.entry(code).or_default().push(answer);
+            }
+        }
+
+        // The next guess either is one of the bucket's candidates or splits the
+        // bucket better than any of them would, so every level shrinks the
+        // buckets and the recursion terminates.
+        let children = buckets
+            .into_par_iter()
+            .map(|(code, bucket)| {
+                let next = self.next_guess(&bucket, guesses + 1);
+                (decode(code), self.build_node(next, &bucket, guesses + 1))
+            })
+            .collect();
+
+        TreeNode {
+            guess: self.matrix.guesses()[guess].clone(),
+            is_answer,
+            children,
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use crate::endgame::EndgameConfig;
+use crate::patterns::PatternMatrix;
+use crate::ranking::RankMethod;
+use crate::strategy::{self, Options};
+use crate::tree::TreeBuilder;
+use crate::error::{Error, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use std::cmp::Ordering;
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// Per-turn `(w_pos, w_overall, w_unique)` weights, as in `solver_config.json`.
+pub type Weights = Vec<(f64, f64, f64)>;
+
+/// How the weight space is searched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Search {
+    /// Tries every point of a grid for one turn at a time, keeping the other
+    /// turns at the best weights so far (one sweep over the turns per iteration).
+    Grid,
+    /// Evaluates completely random weights.
+    Random,
+    /// Keeps the best configs of every generation and mutates them.
+    Evolve,
+}
+
+impl FromStr for Search {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "grid" => Ok(Self::Grid),
+            "random" => Ok(Self::Random),
+            "evolve" => Ok(Self::Evolve),
+            _ => Err(Error::UnknownName { kind: "search", name: s.to_string(), expected: "grid, random or evolve" }),
+        }
+    }
+}
+
+impl Search {
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Grid => "grid",
+            Self::Random => "random",
+            Self::Evolve => "evolve",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TuneConfig {
+    pub search: Search,
+    /// Grid sweeps, random samples or generations.
+    pub iterations: usize,
+    /// Configs per generation of the evolutionary search.
+    pub population: usize,
+    /// Grid spacing, and the largest mutation of a single weight.
+    pub step: f64,
+    pub seed: u64,
+}
+
+impl Default for TuneConfig {
+    fn default() -> Self {
+        Self {
+            search: Search::Evolve,
+            iterations: 20,
+            population: 16,
+            step: 0.1,
+            seed: 0,
+        }
+    }
+}
+
+/// Result of simulating the solver over all answers with some weights.
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub weights: Weights,
+    pub average: f64,
+    pub max_guesses: usize,
+    pub failures: usize,
+}
+
+impl Evaluation {
+    /// Fewer failures first, then fewer guesses on average.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.failures
+            .cmp(&other.failures)
+            .then(self.average.total_cmp(&other.average))
+            .then(self.max_guesses.cmp(&other.max_guesses))
+    }
+}
+
+/// What a tuning run found: the starting point, the best weights, and every
+/// improvement along the way as `(evaluations so far, evaluation)`.
+pub struct TuneResult {
+    pub baseline: Evaluation,
+    pub best: Evaluation,
+    pub improvements: Vec<(usize, Evaluation)>,
+    pub evaluations: usize,
+    /// Iterations run, fewer than asked for once the grid search stops improving.
+    pub iterations: usize,
+}
+
+/// Searches the weights of the weighted strategy (on its own or as the base of
+/// the lookahead) by building the solver's decision tree over all answers for
+/// every candidate config.
+pub struct Tuner<'a> {
+    matrix: &'a PatternMatrix,
+    method: RankMethod,
+    options: Options,
+    endgame: EndgameConfig,
+    hard_mode: bool,
+    /// Answers left out of the simulation, e.g. already used ones.
+    excluded: Vec<String>,
+    /// Fixed first guess; otherwise the strategy picks it with the first turn's weights.
+    opener: Option<String>,
+    config: TuneConfig,
+}
+
+impl<'a> Tuner<'a> {
+    /// Fails if `method` doesn't use the weights.
+    pub fn new(
+        matrix: &'a PatternMatrix,
+        method: RankMethod,
+        options: Options,
+        endgame: EndgameConfig,
+        opener: Option<String>,
+        config: TuneConfig,
+    ) -> Result<Self> {
+        let weighted = match method {
+            RankMethod::Lookahead => options.lookahead.base == RankMethod::Weighted,
+            method => method == RankMethod::Weighted,
+        };
+        if !weighted {
+            return Err(Error::Invalid(
+                "Only the weighted strategy, or the lookahead based on it, uses the weights to tune".to_string(),
+            ));
+        }
+
+        Ok(Self {
+            matrix,
+            method,
+            options,
+            endgame,
+            hard_mode: false,
+            excluded: Vec::new(),
+            opener,
+            config,
+        })
+    }
+
+    /// Simulates the solver in hard mode, like the config's `hard_mode`.
+    pub fn set_hard_mode(&mut self, hard_mode: bool) {
+        self.hard_mode = hard_mode;
+    }
+
+    /// Leaves these answers out of every simulation, like `TreeBuilder::exclude_answers`.
+    pub fn exclude_answers(&mut self, words: &[&str]) {
+        self.excluded = words.iter().map(|w| w.to_string()).collect();
+    }
+
+    pub fn evaluate(&self, weights: &Weights) -> Result<Evaluation> {
+        let mut options = self.options.clone();
+        options.weights = weights.clone();
+        let strategy = strategy::build(self.method, &options)?;
+
+        let mut builder = TreeBuilder::new(self.matrix, strategy.as_ref());
+        builder.set_endgame(self.endgame);
+        builder.set_hard_mode(self.hard_mode);
+        builder.exclude_answers(&self.excluded.iter().map(String::as_str).collect::<Vec<_>>());
+        let opener = self.opener.as_deref().unwrap_or_else(|| builder.opener());
+        let stats = builder.build(opener)?.stats();
+
+        Ok(Evaluation {
+            weights: weights.clone(),
+            average: stats.average(),
+            max_guesses: stats.max_guesses,
+            failures: stats.failures,
+        })
+    }
+
+    /// Runs the search starting from `baseline`, which also fixes the number of
+    /// turns. `progress` is called with the result so far after every iteration.
+    pub fn run(&self, baseline: &Weights, mut progress: impl FnMut(&TuneResult)) -> Result<TuneResult> {
+        if baseline.is_empty() {
+            return Err(Error::Invalid("Tuning needs at least one turn of weights to start from".to_string()));
+        }
+
+        let baseline = self.evaluate(baseline)?;
+        let mut result = TuneResult {
+            best: baseline.clone(),
+            baseline,
+            improvements: Vec::new(),
+            evaluations: 1,
+            iterations: 0,
+        };
+        let mut rng = StdRng::seed_from_u64(self.config.seed);
+
+        for iteration in 0..self.config.iterations {
+            let improvements = result.improvements.len();
+            result.iterations = iteration + 1;
+            match self.config.search {
+                Search::Grid => {
+                    // The first turn only picks the opener, so skip it when that is fixed
+                    let first = self.opener.is_some() as usize;
+                    for turn in first..result.best.weights.len() {
+                        let batch: Vec<Weights> = simplex_grid(self.config.step)
+                            .into_iter()
+                            .map(|point| {
+                                let mut weights = result.best.weights.clone();
+                                weights[turn] = point;
+                                weights
+                            })
+                            .collect();
+                        self.evaluate_batch(&batch, &mut result)?;
+                    }
+                }
+                Search::Random => {
+                    let batch: Vec<Weights> = (0..self.config.population)
+                        .map(|_| (0..result.best.weights.len()).map(|_| random_point(&mut rng)).collect())
+                        .collect();
+                    self.evaluate_batch(&batch, &mut result)?;
+                }
+                Search::Evolve => {
+                    let parents = self.parents(&result);
+                    let batch: Vec<Weights> = (0..self.config.population)
+                        .map(|i| mutate(&parents[i % parents.len()], self.config.step, &mut rng))
+                        .collect();
+                    self.evaluate_batch(&batch, &mut result)?;
+                }
+            }
+
+            progress(&result);
+
+            // The grid is deterministic, so a sweep without improvement would be repeated as is
+            if self.config.search == Search::Grid && result.improvements.len() == improvements {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// The configs the next generation is mutated from: the best few seen so far.
+    fn parents(&self, result: &TuneResult) -> Vec<Weights> {
+        let elite = (self.config.population / 4).max(1);
+        let mut parents: Vec<Weights> = result
+            .improvements
+            .iter()
+            .rev()
+            .take(elite)
+            .map(|(_, evaluation)| evaluation.weights.clone())
+            .collect();
+        if parents.is_empty() {
+            parents.push(result.best.weights.clone());
+        }
+        parents
+    }
+
+    /// Evaluates the configs in parallel and records every one that beats the best so far.
+    fn evaluate_batch(&self, batch: &[Weights], result: &mut TuneResult) -> Result<()> {
+        let evaluations: Vec<Evaluation> = batch
+            .par_iter()
+            .map(|weights| self.evaluate(weights))
+            .collect::<Result<_>>()?;
+
+        for evaluation in evaluations {
+            result.evaluations += 1;
+            if evaluation.cmp(&result.best) == Ordering::Less {
+                result.best = evaluation.clone();
+                result.improvements.push((result.evaluations, evaluation));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Every `(w_pos, w_overall, w_unique)` summing to 1 with components on a grid of `step`.
+fn simplex_grid(step: f64) -> Vec<(f64, f64, f64)> {
+    let steps = (1.0 / step).round() as usize;
+    let mut points = Vec::new();
+    for i in 0..=steps {
+        for j in 0..=steps - i {
+            let k = steps - i - j;
+            points.push(normalize((i as f64, j as f64, k as f64)));
+        }
+    }
+    points
+}
+
+/// Uniformly distributed weights summing to 1.
+fn random_point(rng: &mut StdRng) -> (f64, f64, f64) {
+    let (a, b): (f64, f64) = (rng.random(), rng.random());
+    let (low, high) = if a < b { (a, b) } else { (b, a) };
+    normalize((low, high - low, 1.0 - high))
+}
+
+/// Moves every weight by up to `step` in either direction.
+fn mutate(weights: &Weights, step: f64, rng: &mut StdRng) -> Weights {
+    weights
+        .iter()
+        .map(|&(pos, overall, unique)| {
+            let mut nudge = |w: f64| (w + rng.random_range(-step..=step)).max(0.0);
+            normalize((nudge(pos), nudge(overall), nudge(unique)))
+        })
+        .collect()
+}
+
+/// Scales the weights to sum to 1 and rounds them to three decimals, so
+/// the written config reproduces the evaluated one exactly.
+fn normalize((pos, overall, unique): (f64, f64, f64)) -> (f64, f64, f64) {
+    let total = pos + overall + unique;
+    if total == 0.0 {
+        return (1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0);
+    }
+    let round = |w: f64| (w / total * 1000.0).round() / 1000.0;
+    (round(pos), round(overall), round(unique))
+}
+
+/// Markdown report of a tuning run.
+pub fn report(result: &TuneResult, config: &TuneConfig, opener: Option<&str>) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Weight tuning report\n");
+    let _ = writeln!(out, "* Search: {} ({} of {} iterations, population {}, step {}, seed {})",
+        config.search.label(), result.iterations, config.iterations, config.population, config.step, config.seed);
+    let _ = writeln!(out, "* Opener: {}", opener.unwrap_or("picked by the first turn's weights"));
+    let _ = writeln!(out, "* Configs evaluated: {}\n", result.evaluations);
+
+    let _ = writeln!(out, "| Config   | Average | Worst | Failures |");
+    let _ = writeln!(out, "| -------- | ------- | ----- | -------- |");
+    for (name, evaluation) in [("Baseline", &result.baseline), ("Best", &result.best)] {
+        let _ = writeln!(out, "| {:<8} | {:.4}  | {:>5} | {:>8} |",
+            name, evaluation.average, evaluation.max_guesses, evaluation.failures);
+    }
+
+    let _ = writeln!(out, "\n## Best weights\n");
+    let _ = writeln!(out, "| Turn | w_pos | w_overall | w_unique |");
+    let _ = writeln!(out, "| ---- | ----- | --------- | -------- |");
+    for (turn, (pos, overall, unique)) in result.best.weights.iter().enumerate() {
+        let _ = writeln!(out, "| {} | {} | {} | {} |", turn + 1, pos, overall, unique);
+    }
+
+    let _ = writeln!(out, "\n## Improvements\n");
+    if result.improvements.is_empty() {
+        let _ = writeln!(out, "No config beat the baseline.");
+    } else {
+        let _ = writeln!(out, "| Evaluation | Average | Worst | Failures |");
+        let _ = writeln!(out, "| ---------- | ------- | ----- | -------- |");
+        for (evaluations, evaluation) in &result.improvements {
+            let _ = writeln!(out, "| {} | {:.4} | {} | {} |",
+                evaluations, evaluation.average, evaluation.max_guesses, evaluation.failures);
+        }
+    }
+
+    out
+}
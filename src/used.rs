@@ -0,0 +1,224 @@
+use crate::error::{self, Error, Result};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default location of the used answers list.
+pub const USED_PATH: &str = "used_answers.txt";
+
+/// What to do with answers that were already used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UsedMode {
+    /// Drop them from the candidates.
+    Exclude,
+    /// Keep them, but multiply their likelihood by the given factor.
+    Downweight(f64),
+}
+
+/// Past answers by date, read from a text file with one `YYYY-MM-DD word`
+/// entry per line.
+#[derive(Debug, Clone, Default)]
+pub struct UsedAnswers {
+    entries: BTreeMap<String, String>,
+}
+
+impl UsedAnswers {
+    pub fn load(path: &str) -> Result<Self> {
+        let content = error::read_file(path)?;
+
+        let mut entries = BTreeMap::new();
+        for (number, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let syntax = |message: String| Error::Syntax { path: path.to_string(), line: number + 1, message };
+            let mut fields = line.split_whitespace();
+            let (Some(date), Some(word), None) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(syntax(format!("expected '<YYYY-MM-DD> <word>', got '{}'", line)));
+            };
+            check_date(date).map_err(|e| syntax(e.to_string()))?;
+
+            entries.insert(date.to_string(), word.to_lowercase());
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Like `load`, but a missing file is an empty list.
+    pub fn load_or_default(path: &str) -> Result<Self> {
+        if Path::new(path).exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.entries.values().any(|w| w == word)
+    }
+
+    /// The entries dated strictly before `date`, e.g. the answers already
+    /// used when `date`'s answer was picked.
+    pub fn before(&self, date: &str) -> Self {
+        let entries = self.entries.range(..date.to_string()).map(|(d, w)| (d.clone(), w.clone())).collect();
+        Self { entries }
+    }
+
+    /// Entries ordered by date.
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(date, word)| (date.as_str(), word.as_str()))
+    }
+
+    /// Records `word` as the answer of `date` and appends it to the file.
+    /// Recording the same answer twice is a no-op; a different one for a date
+    /// that already has an answer is an error.
+    pub fn append(&mut self, path: &str, date: &str, word: &str) -> Result<bool> {
+        check_date(date)?;
+        match self.entries.get(date) {
+            Some(existing) if existing == word => return Ok(false),
+            Some(existing) => {
+                return Err(Error::Invalid(format!("{} already has the answer '{}' in {}", date, existing, path)));
+            }
+            None => {}
+        }
+
+        let io_error = |source| Error::Io { path: path.to_string(), source };
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+        writeln!(file, "{} {}", date, word).map_err(io_error)?;
+
+        self.entries.insert(date.to_string(), word.to_string());
+        Ok(true)
+    }
+}
+
+impl FromStr for UsedMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "exclude" => Ok(Self::Exclude),
+            "downweight" => Ok(Self::Downweight(0.05)),
+            _ => Err(Error::UnknownName { kind: "used answers mode", name: s.to_string(), expected: "exclude or downweight" }),
+        }
+    }
+}
+
+/// Today's date in UTC as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Number of days between 1970-01-01 and `date` (`YYYY-MM-DD`).
+pub fn days_from_civil(date: &str) -> Result<i64> {
+    let (year, month, day) = check_date(date)?;
+    // Howard Hinnant's algorithm, with years starting in March
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Ok(era * 146_097 + day_of_era - 719_468)
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn check_date(date: &str) -> Result<(i64, i64, i64)> {
+    let invalid = || Error::InvalidDate(date.to_string());
+
+    let parts: Vec<&str> = date.split('-').collect();
+    let [year, month, day] = parts.as_slice() else {
+        return Err(invalid());
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return Err(invalid());
+    }
+
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: i64 = month.parse().map_err(|_| invalid())?;
+    let day: i64 = day.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return Err(invalid());
+    }
+
+    Ok((year, month, day))
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_dates() {
+        assert_eq!(days_from_civil("1970-01-01").unwrap(), 0);
+        assert_eq!(days_from_civil("1970-01-02").unwrap(), 1);
+        assert_eq!(days_from_civil("1969-12-31").unwrap(), -1);
+        assert_eq!(days_from_civil("2000-03-01").unwrap(), 11_017);
+        assert_eq!(days_from_civil("2021-06-19").unwrap(), 18_797);
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(18_797), (2021, 6, 19));
+    }
+
+    #[test]
+    fn round_trips() {
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            if !(0..=9999).contains(&year) {
+                continue;
+            }
+            let date = format!("{:04}-{:02}-{:02}", year, month, day);
+            assert_eq!(days_from_civil(&date).unwrap(), days, "{}", date);
+        }
+    }
+
+    #[test]
+    fn leap_years() {
+        assert_eq!(check_date("2024-02-29").unwrap(), (2024, 2, 29));
+        assert!(check_date("2000-02-29").is_ok());
+        assert!(check_date("2026-02-29").is_err());
+        assert!(check_date("1900-02-29").is_err());
+        assert_eq!(days_from_civil("2024-03-01").unwrap() - days_from_civil("2024-02-28").unwrap(), 2);
+    }
+
+    #[test]
+    fn invalid_dates() {
+        let dates = [
+            "2026-13-01", "2026-00-10", "2026-04-31", "2026-01-00", "2026-1-01", "26-01-01", "2026/01/01",
+            "2026-01-01-01", "today", "",
+        ];
+        for date in dates {
+            assert!(matches!(check_date(date), Err(Error::InvalidDate(d)) if d == date), "{}", date);
+        }
+    }
+}
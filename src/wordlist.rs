@@ -0,0 +1,31 @@
+use crate::error::{self, Error, Result};
+
+/// Default location of the word list.
+pub const WORDLIST_PATH: &str = "wordlist.txt";
+
+/// Index in `wordlist.txt` where the possible answers start. Everything before
+/// it is only an allowed guess.
+pub const ANSWERS_START: usize = 10657;
+
+/// Reads `wordlist.txt` and returns all 5-letter words, lowercased. Lines with
+/// anything but the letters a-z are skipped.
+pub fn load_words() -> Result<Vec<String>> {
+    let content = error::read_file(WORDLIST_PATH)?;
+
+    let words: Vec<String> = content
+        .lines()
+        .map(|w| w.trim().to_lowercase())
+        .filter(|w| w.len() == 5 && w.bytes().all(|b| b.is_ascii_lowercase()))
+        .collect();
+
+    if words.is_empty() {
+        return Err(Error::EmptyWordList(WORDLIST_PATH.to_string()));
+    }
+
+    Ok(words)
+}
+
+/// Returns the part of the word list that can be an answer.
+pub fn answers(words: &[String]) -> &[String] {
+    &words[ANSWERS_START.min(words.len())..]
+}
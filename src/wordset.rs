@@ -0,0 +1,169 @@
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+
+/// A word as its five lowercase ASCII letters.
+pub type Word = [u8; 5];
+
+/// A fixed-size set of word indices, one bit per word.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Bitset {
+    blocks: Vec<u64>,
+    len: usize,
+}
+
+impl Bitset {
+    pub fn empty(len: usize) -> Self {
+        Self {
+            blocks: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    pub fn full(len: usize) -> Self {
+        let mut set = Self::empty(len);
+        for block in &mut set.blocks {
+            *block = u64::MAX;
+        }
+        set.clear_tail();
+        set
+    }
+
+    pub fn insert(&mut self, index: usize) {
+        self.blocks[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn contains(&self, index: usize) -> bool {
+        self.blocks[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn count(&self) -> usize {
+        self.blocks.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    /// Keeps only the indices also in `other`.
+    pub fn intersect_with(&mut self, other: &Bitset) {
+        for (block, other) in self.blocks.iter_mut().zip(&other.blocks) {
+            *block &= other;
+        }
+    }
+
+    /// Removes all indices that are in `other`.
+    pub fn difference_with(&mut self, other: &Bitset) {
+        for (block, other) in self.blocks.iter_mut().zip(&other.blocks) {
+            *block &= !other;
+        }
+    }
+
+    /// Iterates over the contained indices in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.blocks.iter().enumerate().flat_map(|(i, &block)| {
+            let mut bits = block;
+            std::iter::from_fn(move || {
+                if bits == 0 {
+                    return None;
+                }
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+
+    fn clear_tail(&mut self) {
+        let rest = self.len % 64;
+        if rest != 0 {
+            if let Some(last) = self.blocks.last_mut() {
+                *last &= (1 << rest) - 1;
+            }
+        }
+    }
+}
+
+/// A word list stored as byte arrays, with a bitmask per letter and per
+/// (position, letter) so constraints can be applied with bitwise operations.
+pub struct WordSet {
+    words: Vec<Word>,
+    index: HashMap<Word, usize>,
+    has_letter: Vec<Bitset>,
+    letter_at: Vec<[Bitset; 26]>,
+}
+
+impl WordSet {
+    /// Builds the set from 5-letter lowercase words, as returned by `wordlist::load_words`.
+    pub fn new(words: &[String]) -> Result<Self> {
+        let words: Vec<Word> = words
+            .iter()
+            .map(|w| {
+                w.as_bytes()
+                    .try_into()
+                    .ok()
+                    .filter(|word: &Word| word.iter().all(|b| b.is_ascii_lowercase()))
+                    .ok_or_else(|| Error::InvalidWord(w.clone()))
+            })
+            .collect::<Result<_>>()?;
+
+        let len = words.len();
+        let mut has_letter = vec![Bitset::empty(len); 26];
+        let mut letter_at: Vec<[Bitset; 26]> = (0..5)
+            .map(|_| std::array::from_fn(|_| Bitset::empty(len)))
+            .collect();
+        let mut index = HashMap::with_capacity(len);
+
+        for (i, word) in words.iter().enumerate() {
+            index.insert(*word, i);
+            for (pos, &letter) in word.iter().enumerate() {
+                let letter = (letter - b'a') as usize;
+                has_letter[letter].insert(i);
+                letter_at[pos][letter].insert(i);
+            }
+        }
+
+        Ok(Self { words, index, has_letter, letter_at })
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn word(&self, index: usize) -> &Word {
+        &self.words[index]
+    }
+
+    pub fn as_str(&self, index: usize) -> &str {
+        std::str::from_utf8(&self.words[index]).expect("words are ASCII")
+    }
+
+    pub fn index_of(&self, word: &str) -> Option<usize> {
+        let word: Word = word.as_bytes().try_into().ok()?;
+        self.index.get(&word).copied()
+    }
+
+    /// Every word index.
+    pub fn all(&self) -> Bitset {
+        Bitset::full(self.len())
+    }
+
+    /// Words containing `letter` anywhere.
+    pub fn with_letter(&self, letter: char) -> &Bitset {
+        &self.has_letter[letter_index(letter)]
+    }
+
+    /// Words with `letter` at position `pos`.
+    pub fn with_letter_at(&self, pos: usize, letter: char) -> &Bitset {
+        &self.letter_at[pos][letter_index(letter)]
+    }
+
+    /// Borrows the words of `set` as string slices, in index order.
+    pub fn strs(&self, set: &Bitset) -> Vec<&str> {
+        set.iter().map(|i| self.as_str(i)).collect()
+    }
+}
+
+fn letter_index(letter: char) -> usize {
+    debug_assert!(letter.is_ascii_lowercase());
+    (letter as u8 - b'a') as usize
+}